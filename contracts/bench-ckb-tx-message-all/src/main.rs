@@ -0,0 +1,53 @@
+#![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(any(feature = "native-simulator", test))]
+extern crate alloc;
+
+#[cfg(not(any(feature = "native-simulator", test)))]
+ckb_std::entry!(program_entry);
+#[cfg(not(any(feature = "native-simulator", test)))]
+// A large heap is required if we want to load the witness as a whole
+ckb_std::default_alloc!(16384, 2097152, 64);
+
+use alloc::vec;
+use ckb_gen_types::{packed::WitnessArgsReader, prelude::*};
+use ckb_std::{ckb_constants::Source, high_level};
+use ckb_tx_message_all_utils::{
+    ckb_tx_message_all_in_ckb_vm::{
+        generate_ckb_tx_message_all_with_buffer, DEFAULT_LOAD_BUFFER_LENGTH,
+    },
+    hasher::Hasher,
+};
+
+pub fn program_entry() -> i8 {
+    // Script args, when present, contain the load buffer length to use
+    // as a little endian u32 value.
+    let args = high_level::load_script().expect("load script").args();
+    let buffer_length = match args.raw_data().as_ref().try_into() {
+        Ok(data) => u32::from_le_bytes(data) as usize,
+        Err(_) => DEFAULT_LOAD_BUFFER_LENGTH,
+    };
+    let mut buffer = vec![0u8; buffer_length];
+
+    let mut hasher = Hasher::default();
+    if let Err(e) = generate_ckb_tx_message_all_with_buffer(&mut hasher, &mut buffer) {
+        ckb_std::debug!("Generate CKB_TX_MESSAGE_ALL encounters error: {:?}", e);
+        return 99;
+    }
+    let hash = hasher.hash();
+
+    let first_witness_data =
+        high_level::load_witness(0, Source::GroupInput).expect("load first witness data");
+    let first_witness = WitnessArgsReader::from_slice(&first_witness_data)
+        .expect("first witness is not WitnessArgs");
+
+    let lock_data = first_witness
+        .lock()
+        .to_opt()
+        .expect("lock is empty")
+        .raw_data();
+    assert_eq!(lock_data, hash);
+
+    0
+}
@@ -0,0 +1,29 @@
+#ifndef CKB_TX_MESSAGE_EXIT_CODES_H_
+#define CKB_TX_MESSAGE_EXIT_CODES_H_
+
+/*
+ * Exit codes of the CKB_TX_MESSAGE_ALL assert scripts, mirroring ExitCode in
+ * ckb-tx-message-all-utils, so C & Rust scripts report the same failure with
+ * the same code.
+ */
+
+/* The lock field does not contain the CKB_TX_MESSAGE_ALL hash */
+#define ERROR_HASH_MISMATCH 1
+/* The lock field is not a 32-byte hash */
+#define ERROR_WRONG_LOCK_LENGTH 2
+/* Script args do not follow the layout expected by the script */
+#define ERROR_INVALID_ARGS 3
+/* Script args denote a sighash mode that is not supported */
+#define ERROR_UNKNOWN_SIGHASH_MODE 94
+/* Script args denote a hash algorithm that is not supported */
+#define ERROR_UNKNOWN_HASH_ALGORITHM 95
+/* A syscall fails, e.g., current script group has no witness */
+#define ERROR_SYSCALL 96
+/* The first witness of current script group is not a valid WitnessArgs */
+#define ERROR_INVALID_WITNESS_ARGS 97
+/* The first witness of current script group has no lock field */
+#define ERROR_MISSING_LOCK 98
+/* CKB_TX_MESSAGE_ALL cannot be generated for other reasons */
+#define ERROR_GENERATION 99
+
+#endif /* CKB_TX_MESSAGE_EXIT_CODES_H_ */
@@ -0,0 +1,79 @@
+#ifndef CKB_TX_MESSAGE_HASHER_H_
+#define CKB_TX_MESSAGE_HASHER_H_
+
+/*
+ * A hasher producing 32-byte hashes using one of the supported algorithms.
+ * Algorithm ids match HashAlgorithm in ckb-tx-message-all-utils.
+ */
+
+#include <blake2b.h>
+
+#include "keccak256.h"
+#include "sha256.h"
+
+#define CKB_TX_MESSAGE_HASH_CKB_BLAKE2B 0
+#define CKB_TX_MESSAGE_HASH_SHA256 1
+#define CKB_TX_MESSAGE_HASH_KECCAK256 2
+
+typedef struct {
+  uint8_t algorithm;
+  union {
+    blake2b_state blake2b;
+    sha256_state sha256;
+    keccak256_state keccak256;
+  } state;
+} ckb_tx_message_hasher_t;
+
+/* Returns non-zero when the algorithm is not supported */
+static int ckb_tx_message_hasher_init(ckb_tx_message_hasher_t* hasher,
+                                      uint8_t algorithm) {
+  hasher->algorithm = algorithm;
+  switch (algorithm) {
+    case CKB_TX_MESSAGE_HASH_CKB_BLAKE2B:
+      ckb_blake2b_init(&hasher->state.blake2b, 32);
+      return 0;
+    case CKB_TX_MESSAGE_HASH_SHA256:
+      sha256_init(&hasher->state.sha256);
+      return 0;
+    case CKB_TX_MESSAGE_HASH_KECCAK256:
+      keccak256_init(&hasher->state.keccak256);
+      return 0;
+    default:
+      return -1;
+  }
+}
+
+/* Can be used as the writer of ckb_tx_message_all_generate */
+static int ckb_tx_message_hasher_write(const uint8_t* data, size_t length,
+                                       void* context) {
+  ckb_tx_message_hasher_t* hasher = (ckb_tx_message_hasher_t*)context;
+  switch (hasher->algorithm) {
+    case CKB_TX_MESSAGE_HASH_CKB_BLAKE2B:
+      blake2b_update(&hasher->state.blake2b, data, length);
+      break;
+    case CKB_TX_MESSAGE_HASH_SHA256:
+      sha256_update(&hasher->state.sha256, data, length);
+      break;
+    case CKB_TX_MESSAGE_HASH_KECCAK256:
+      keccak256_update(&hasher->state.keccak256, data, length);
+      break;
+  }
+  return 0;
+}
+
+static void ckb_tx_message_hasher_final(ckb_tx_message_hasher_t* hasher,
+                                        uint8_t out[32]) {
+  switch (hasher->algorithm) {
+    case CKB_TX_MESSAGE_HASH_CKB_BLAKE2B:
+      blake2b_final(&hasher->state.blake2b, out, 32);
+      break;
+    case CKB_TX_MESSAGE_HASH_SHA256:
+      sha256_final(&hasher->state.sha256, out);
+      break;
+    case CKB_TX_MESSAGE_HASH_KECCAK256:
+      keccak256_final(&hasher->state.keccak256, out);
+      break;
+  }
+}
+
+#endif /* CKB_TX_MESSAGE_HASHER_H_ */
@@ -0,0 +1,119 @@
+#ifndef CKB_TX_MESSAGE_KECCAK256_H_
+#define CKB_TX_MESSAGE_KECCAK256_H_
+
+/*
+ * A minimal Keccak-256 implementation. Note this is the original Keccak
+ * padding(0x01) as used by Ethereum, not the finalized SHA3-256 padding(0x06).
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+#define _KECCAK256_RATE 136
+
+typedef struct {
+  uint64_t state[25];
+  uint8_t buffer[_KECCAK256_RATE];
+  size_t buffer_length;
+} keccak256_state;
+
+static const uint64_t _keccak_round_constants[24] = {
+    0x0000000000000001ULL, 0x0000000000008082ULL, 0x800000000000808aULL,
+    0x8000000080008000ULL, 0x000000000000808bULL, 0x0000000080000001ULL,
+    0x8000000080008081ULL, 0x8000000000008009ULL, 0x000000000000008aULL,
+    0x0000000000000088ULL, 0x0000000080008009ULL, 0x000000008000000aULL,
+    0x000000008000808bULL, 0x800000000000008bULL, 0x8000000000008089ULL,
+    0x8000000000008003ULL, 0x8000000000008002ULL, 0x8000000000000080ULL,
+    0x000000000000800aULL, 0x800000008000000aULL, 0x8000000080008081ULL,
+    0x8000000000008080ULL, 0x0000000080000001ULL, 0x8000000080008008ULL};
+
+static const unsigned _keccak_rotations[24] = {
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14,
+    27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44};
+
+static const unsigned _keccak_pi_lanes[24] = {
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4,
+    15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1};
+
+#define _KECCAK_ROTL(x, n) (((x) << (n)) | ((x) >> (64 - (n))))
+
+static void _keccak_f1600(uint64_t s[25]) {
+  uint64_t bc[5];
+  for (int round = 0; round < 24; round++) {
+    /* Theta */
+    for (int i = 0; i < 5; i++) {
+      bc[i] = s[i] ^ s[i + 5] ^ s[i + 10] ^ s[i + 15] ^ s[i + 20];
+    }
+    for (int i = 0; i < 5; i++) {
+      uint64_t t = bc[(i + 4) % 5] ^ _KECCAK_ROTL(bc[(i + 1) % 5], 1);
+      for (int j = 0; j < 25; j += 5) {
+        s[j + i] ^= t;
+      }
+    }
+    /* Rho & Pi */
+    uint64_t t = s[1];
+    for (int i = 0; i < 24; i++) {
+      unsigned j = _keccak_pi_lanes[i];
+      uint64_t current = s[j];
+      s[j] = _KECCAK_ROTL(t, _keccak_rotations[i]);
+      t = current;
+    }
+    /* Chi */
+    for (int j = 0; j < 25; j += 5) {
+      for (int i = 0; i < 5; i++) {
+        bc[i] = s[j + i];
+      }
+      for (int i = 0; i < 5; i++) {
+        s[j + i] ^= (~bc[(i + 1) % 5]) & bc[(i + 2) % 5];
+      }
+    }
+    /* Iota */
+    s[0] ^= _keccak_round_constants[round];
+  }
+}
+
+/* Lanes are little endian */
+static void _keccak256_absorb(keccak256_state* s) {
+  for (int i = 0; i < _KECCAK256_RATE; i++) {
+    s->state[i / 8] ^= (uint64_t)s->buffer[i] << (8 * (i % 8));
+  }
+  _keccak_f1600(s->state);
+  s->buffer_length = 0;
+}
+
+static void keccak256_init(keccak256_state* s) {
+  memset(s->state, 0, sizeof(s->state));
+  s->buffer_length = 0;
+}
+
+static void keccak256_update(keccak256_state* s, const uint8_t* data,
+                             size_t length) {
+  while (length > 0) {
+    size_t n = _KECCAK256_RATE - s->buffer_length;
+    if (n > length) {
+      n = length;
+    }
+    memcpy(&s->buffer[s->buffer_length], data, n);
+    s->buffer_length += n;
+    data += n;
+    length -= n;
+    if (s->buffer_length == _KECCAK256_RATE) {
+      _keccak256_absorb(s);
+    }
+  }
+}
+
+static void keccak256_final(keccak256_state* s, uint8_t out[32]) {
+  memset(&s->buffer[s->buffer_length], 0,
+         _KECCAK256_RATE - s->buffer_length);
+  s->buffer[s->buffer_length] ^= 0x01;
+  s->buffer[_KECCAK256_RATE - 1] ^= 0x80;
+  _keccak256_absorb(s);
+
+  for (int i = 0; i < 32; i++) {
+    out[i] = (uint8_t)(s->state[i / 8] >> (8 * (i % 8)));
+  }
+}
+
+#endif /* CKB_TX_MESSAGE_KECCAK256_H_ */
@@ -1,50 +1,96 @@
 #define MOL2_EXIT ckb_exit
 
-#include <blake2b.h>
-
 #include "ckb_tx_message_all.h"
+#include "exit_codes.h"
+#include "hasher.h"
 
 #define WITNESS_BUFFER_SIZE (1024 * 32)
+#define SCRIPT_SIZE (1024 * 32)
+
+/*
+ * The first byte of script args, if any, selects the hash algorithm, empty
+ * args denote CKB flavored blake2b. The second byte, if any, selects the
+ * sighash mode, only 0(CKB_TX_MESSAGE_ALL itself) is supported here.
+ *
+ * Script is a molecule table of code_hash, hash_type and args. Being the
+ * last field, args(in Bytes structure) spans from its offset, which is the
+ * third one in the table header, till the end of the script.
+ */
+int load_script_args(uint8_t* algorithm, uint8_t* sighash_mode) {
+  uint8_t script[SCRIPT_SIZE];
+  uint64_t len = SCRIPT_SIZE;
+  int err = ckb_load_script(script, &len, 0);
+  if (err != 0) {
+    return err;
+  }
+  if (len > SCRIPT_SIZE || len < 16) {
+    return MOL2_ERR_DATA;
+  }
+  uint32_t full_size, args_offset, args_length;
+  memcpy(&full_size, script, 4);
+  memcpy(&args_offset, &script[12], 4);
+  if (full_size != len || args_offset > len - 4) {
+    return MOL2_ERR_DATA;
+  }
+  memcpy(&args_length, &script[args_offset], 4);
+  if (args_length != len - args_offset - 4) {
+    return MOL2_ERR_DATA;
+  }
 
-int write_to_blake2b(const uint8_t* data, size_t length, void* context) {
-  blake2b_state* state = (blake2b_state*)context;
-  blake2b_update(state, data, length);
+  *algorithm = (args_length == 0) ? CKB_TX_MESSAGE_HASH_CKB_BLAKE2B
+                                  : script[args_offset + 4];
+  *sighash_mode = (args_length < 2) ? 0 : script[args_offset + 5];
   return 0;
 }
 
 int main() {
-  blake2b_state state;
-  ckb_blake2b_init(&state, 32);
-
-  int err = ckb_tx_message_all_generate(write_to_blake2b, &state);
+  uint8_t algorithm, sighash_mode;
+  int err = load_script_args(&algorithm, &sighash_mode);
   if (err != 0) {
-    ckb_printf("CKB_TX_MESSAGE_ALL encounters error: %d\n", err);
-    return 99;
+    ckb_printf("Loading script encounters error: %d\n", err);
+    return ERROR_SYSCALL;
+  }
+  if (sighash_mode != 0) {
+    ckb_printf("Unknown sighash mode: %u\n", sighash_mode);
+    return ERROR_UNKNOWN_SIGHASH_MODE;
+  }
+
+  ckb_tx_message_hasher_t hasher;
+  if (ckb_tx_message_hasher_init(&hasher, algorithm) != 0) {
+    ckb_printf("Unknown hash algorithm: %u\n", algorithm);
+    return ERROR_UNKNOWN_HASH_ALGORITHM;
   }
 
+  /*
+   * Loading and validating the first witness are separated, so syscall
+   * failures & invalid WitnessArgs are reported with different codes.
+   */
   uint8_t first_witness_buffer[MOL2_DATA_SOURCE_LEN(WITNESS_BUFFER_SIZE)];
   WitnessArgsType first_witness;
-  /* We can skip the validation since CKB_TX_MESSAGE_ALL process takes care of
-   * it */
   err =
       mol2_lazy_witness_args_load(first_witness_buffer, WITNESS_BUFFER_SIZE, 0,
                                   CKB_SOURCE_GROUP_INPUT, 0, &first_witness);
   if (err != 0) {
     ckb_printf("Loading the first witness encounters error: %d\n", err);
-    return err;
+    return ERROR_SYSCALL;
+  }
+  err = mol2_lazy_witness_args_verify(&first_witness, 0);
+  if (err != MOL2_OK) {
+    ckb_printf("The first witness is not a valid WitnessArgs: %d\n", err);
+    return ERROR_INVALID_WITNESS_ARGS;
   }
 
   BytesOptType lock = first_witness.t->lock(&first_witness);
   if (lock.t->is_none(&lock)) {
     ckb_printf("Lock field in WitnessArgs is lacking content!\n");
-    return -1;
+    return ERROR_MISSING_LOCK;
   }
 
   mol2_cursor_t lock_content = lock.t->unwrap(&lock);
   if (lock_content.size != 32) {
     ckb_printf("Lock field has length: %u, which is expected to be 32!\n",
                lock_content.size);
-    return -1;
+    return ERROR_WRONG_LOCK_LENGTH;
   }
 
   uint8_t expected[32];
@@ -52,15 +98,23 @@ int main() {
   if (len != 32) {
     ckb_printf("Read %u bytes of data from lock, which is expected to be 32!\n",
                len);
-    return -1;
+    return ERROR_SYSCALL;
+  }
+
+  /* The already validated first witness is reused */
+  err = ckb_tx_message_all_generate_with_witness_args(
+      ckb_tx_message_hasher_write, &hasher, &first_witness);
+  if (err != 0) {
+    ckb_printf("CKB_TX_MESSAGE_ALL encounters error: %d\n", err);
+    return ERROR_GENERATION;
   }
 
   uint8_t actual[32];
-  blake2b_final(&state, actual, 32);
+  ckb_tx_message_hasher_final(&hasher, actual);
 
   if (memcmp(actual, expected, 32) != 0) {
     ckb_printf("CKB_TX_MESSAGE_ALL does not match!\n");
-    return -1;
+    return ERROR_HASH_MISMATCH;
   }
 
   return 0;
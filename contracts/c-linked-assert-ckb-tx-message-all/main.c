@@ -0,0 +1,77 @@
+#define MOL2_EXIT ckb_exit
+
+#include <ckb_syscalls.h>
+
+/*
+ * CKB_TX_MESSAGE_ALL is generated by the Rust implementation linked from the
+ * ckb-tx-message-all-c static library, ckb_tx_message_all.h is not used.
+ */
+#include "ckb_tx_message_all_rs.h"
+#include "exit_codes.h"
+#include "hasher.h"
+#include "witness_args_lazy_utils.h"
+
+#define WITNESS_BUFFER_SIZE (1024 * 32)
+
+int main() {
+  ckb_tx_message_hasher_t hasher;
+  ckb_tx_message_hasher_init(&hasher, CKB_TX_MESSAGE_HASH_CKB_BLAKE2B);
+
+  /*
+   * The library validates the first witness, its error codes for invalid
+   * WitnessArgs & other failures match exit_codes.h, the rest are syscall
+   * errors since the hasher never fails.
+   */
+  int err = ckb_tx_message_all_generate(ckb_tx_message_hasher_write, &hasher);
+  if (err != 0) {
+    ckb_printf("CKB_TX_MESSAGE_ALL encounters error: %d\n", err);
+    if (err == CKB_TX_MESSAGE_ALL_ERROR_INVALID_WITNESS_ARGS ||
+        err == CKB_TX_MESSAGE_ALL_ERROR_GENERATION) {
+      return err;
+    }
+    return ERROR_SYSCALL;
+  }
+
+  uint8_t first_witness_buffer[MOL2_DATA_SOURCE_LEN(WITNESS_BUFFER_SIZE)];
+  WitnessArgsType first_witness;
+  /* We can skip the validation since CKB_TX_MESSAGE_ALL process takes care of
+   * it */
+  err =
+      mol2_lazy_witness_args_load(first_witness_buffer, WITNESS_BUFFER_SIZE, 0,
+                                  CKB_SOURCE_GROUP_INPUT, 0, &first_witness);
+  if (err != 0) {
+    ckb_printf("Loading the first witness encounters error: %d\n", err);
+    return ERROR_SYSCALL;
+  }
+
+  BytesOptType lock = first_witness.t->lock(&first_witness);
+  if (lock.t->is_none(&lock)) {
+    ckb_printf("Lock field in WitnessArgs is lacking content!\n");
+    return ERROR_MISSING_LOCK;
+  }
+
+  mol2_cursor_t lock_content = lock.t->unwrap(&lock);
+  if (lock_content.size != 32) {
+    ckb_printf("Lock field has length: %u, which is expected to be 32!\n",
+               lock_content.size);
+    return ERROR_WRONG_LOCK_LENGTH;
+  }
+
+  uint8_t expected[32];
+  uint32_t len = mol2_read_at(&lock_content, expected, 32);
+  if (len != 32) {
+    ckb_printf("Read %u bytes of data from lock, which is expected to be 32!\n",
+               len);
+    return ERROR_SYSCALL;
+  }
+
+  uint8_t actual[32];
+  ckb_tx_message_hasher_final(&hasher, actual);
+
+  if (memcmp(actual, expected, 32) != 0) {
+    ckb_printf("CKB_TX_MESSAGE_ALL does not match!\n");
+    return ERROR_HASH_MISMATCH;
+  }
+
+  return 0;
+}
@@ -0,0 +1,22 @@
+#![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(any(feature = "native-simulator", test))]
+extern crate alloc;
+
+#[cfg(not(any(feature = "native-simulator", test)))]
+ckb_std::entry!(program_entry);
+#[cfg(not(any(feature = "native-simulator", test)))]
+// A large heap is required if we want to load the witness as a whole
+ckb_std::default_alloc!(16384, 2097152, 64);
+
+use ckb_tx_message_all_utils::ckb_tx_message_all_oracle::run_ckb_tx_message_all_oracle;
+
+pub fn program_entry() -> i8 {
+    if let Err(e) = run_ckb_tx_message_all_oracle() {
+        ckb_std::debug!("CKB_TX_MESSAGE_ALL oracle encounters error: {:?}", e);
+        return 99;
+    }
+
+    0
+}
@@ -0,0 +1,42 @@
+#![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(any(feature = "native-simulator", test))]
+extern crate alloc;
+
+use ckb_std::ckb_types::core::ScriptHashType;
+use ckb_tx_message_all_lock::{ckb_tx_message_all_lock, Error, VerificationCode};
+use ckb_tx_message_all_utils::verifier::{spawn_verifier, VerifierArgs, VerifierError};
+
+/// Script args are too short
+pub const ERROR_INVALID_ARGS: VerificationCode = VerificationCode::new(1);
+/// The verifier exits with a code that cannot be forwarded, e.g., 0 or a
+/// code reserved by ckb-tx-message-all-lock
+pub const ERROR_INVALID_VERIFIER_EXIT: VerificationCode = VerificationCode::new(93);
+
+ckb_tx_message_all_lock!(verify);
+
+/// Script args contain the data hash of the verifier script(32 bytes), the
+/// signature algorithm id(1 byte), followed by the public key hash. The
+/// latter two are opaque to this script, they are only interpreted by the
+/// verifier.
+fn verify(message: &[u8; 32], lock: &[u8], args: &[u8]) -> Result<(), Error> {
+    if args.len() <= 33 {
+        return Err(Error::Verification(ERROR_INVALID_ARGS));
+    }
+
+    let verifier_args = VerifierArgs {
+        algorithm_id: args[32],
+        pubkey_hash: args[33..].to_vec(),
+        message: *message,
+        signature: lock.to_vec(),
+    };
+    spawn_verifier(&args[0..32], ScriptHashType::Data2, &verifier_args).map_err(|e| match e {
+        VerifierError::Syscall(e) => Error::Syscall(e),
+        // Verifier's exit code is forwarded so the failure reason is kept
+        VerifierError::ChildExit(exit_code) => Error::Verification(
+            VerificationCode::try_from(exit_code).unwrap_or(ERROR_INVALID_VERIFIER_EXIT),
+        ),
+        VerifierError::InvalidArguments => Error::Verification(ERROR_INVALID_ARGS),
+    })
+}
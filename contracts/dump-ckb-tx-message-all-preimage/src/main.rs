@@ -0,0 +1,113 @@
+#![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(any(feature = "native-simulator", test))]
+extern crate alloc;
+
+#[cfg(not(any(feature = "native-simulator", test)))]
+ckb_std::entry!(program_entry);
+#[cfg(not(any(feature = "native-simulator", test)))]
+// A large heap is required if we want to load the witness as a whole
+ckb_std::default_alloc!(16384, 2097152, 64);
+
+use alloc::format;
+use ckb_gen_types::{packed::WitnessArgsReader, prelude::*};
+use ckb_hash::{new_blake2b, Blake2b};
+use ckb_rust_std::io;
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{self, encode_hex},
+    syscalls,
+};
+use ckb_tx_message_all_utils::ckb_tx_message_all_in_ckb_vm::{
+    generate_ckb_tx_message_all, DUMPED_HASH_PREFIX, DUMPED_PREIMAGE_PREFIX,
+};
+
+const CHUNK_LENGTH: usize = 512;
+
+/// A writer that feeds the preimage into a ckb flavored blake2b hasher,
+/// while also dumping it via debug syscalls in fixed-length chunks.
+pub struct PreimageDumper {
+    hasher: Blake2b,
+    chunk: [u8; CHUNK_LENGTH],
+    chunk_length: usize,
+}
+
+impl PreimageDumper {
+    pub fn hash(self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        self.hasher.finalize(&mut result);
+        result
+    }
+
+    fn emit_chunk(&mut self) {
+        if self.chunk_length > 0 {
+            dump(DUMPED_PREIMAGE_PREFIX, &self.chunk[0..self.chunk_length]);
+            self.chunk_length = 0;
+        }
+    }
+}
+
+impl Default for PreimageDumper {
+    fn default() -> Self {
+        PreimageDumper {
+            hasher: new_blake2b(),
+            chunk: [0u8; CHUNK_LENGTH],
+            chunk_length: 0,
+        }
+    }
+}
+
+impl io::Write for PreimageDumper {
+    fn write(&mut self, data: &[u8]) -> Result<usize, io::Error> {
+        self.hasher.update(data);
+
+        let mut remaining = data;
+        while !remaining.is_empty() {
+            let length = core::cmp::min(CHUNK_LENGTH - self.chunk_length, remaining.len());
+            self.chunk[self.chunk_length..self.chunk_length + length]
+                .copy_from_slice(&remaining[0..length]);
+            self.chunk_length += length;
+            remaining = &remaining[length..];
+
+            if self.chunk_length == CHUNK_LENGTH {
+                self.emit_chunk();
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.emit_chunk();
+        Ok(())
+    }
+}
+
+fn dump(prefix: &str, data: &[u8]) {
+    let hex = encode_hex(data).into_string().expect("hex string");
+    syscalls::debug(format!("{}{}", prefix, hex));
+}
+
+pub fn program_entry() -> i8 {
+    let mut dumper = PreimageDumper::default();
+    if let Err(e) = generate_ckb_tx_message_all(&mut dumper) {
+        ckb_std::debug!("Generate CKB_TX_MESSAGE_ALL encounters error: {:?}", e);
+        return 99;
+    }
+    let hash = dumper.hash();
+    dump(DUMPED_HASH_PREFIX, &hash);
+
+    let first_witness_data =
+        high_level::load_witness(0, Source::GroupInput).expect("load first witness data");
+    let first_witness = WitnessArgsReader::from_slice(&first_witness_data)
+        .expect("first witness is not WitnessArgs");
+
+    let lock_data = first_witness
+        .lock()
+        .to_opt()
+        .expect("lock is empty")
+        .raw_data();
+    assert_eq!(lock_data, hash);
+
+    0
+}
@@ -0,0 +1,83 @@
+#![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(any(feature = "native-simulator", test))]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use ckb_hash::blake2b_256;
+use ckb_tx_message_all_lock::{ckb_tx_message_all_lock, Error, VerificationCode};
+use ckb_tx_message_all_utils::secp256k1_blake160::{
+    self, recover_blake160, PUBKEY_HASH_LENGTH, SIGNATURE_LENGTH,
+};
+
+// Codes 1, 3 & 4 follow secp256k1-lock-ckb-tx-message-all.
+pub const ERROR_INVALID_ARGUMENTS: VerificationCode =
+    VerificationCode::new(secp256k1_blake160::ERROR_INVALID_ARGUMENTS);
+pub const ERROR_INVALID_SIGNATURE: VerificationCode =
+    VerificationCode::new(secp256k1_blake160::ERROR_INVALID_SIGNATURE);
+/// A signature is not made by any of the remaining public keys, this covers
+/// unknown signers, duplicate signers, as well as signatures out of order.
+pub const ERROR_PUBKEY_HASH_MISMATCH: VerificationCode =
+    VerificationCode::new(secp256k1_blake160::ERROR_PUBKEY_HASH_MISMATCH);
+pub const ERROR_INVALID_MULTISIG_CONFIG: VerificationCode = VerificationCode::new(5);
+pub const ERROR_MULTISIG_HASH_MISMATCH: VerificationCode = VerificationCode::new(6);
+/// Not all of the first R public keys have signed
+pub const ERROR_REQUIRED_SIGNER_MISSING: VerificationCode = VerificationCode::new(7);
+/// The lock field does not contain exactly M signatures
+pub const ERROR_WRONG_SIGNATURE_COUNT: VerificationCode = VerificationCode::new(8);
+
+ckb_tx_message_all_lock!(verify);
+
+/// Script args contain the first 20 bytes of the ckb flavored blake2b hash
+/// of the multisig config. Following the system multisig script, the lock
+/// field contains the config: S(reserved, must be 0) | R | M | N | N public
+/// key hashes, followed by M secp256k1 recoverable signatures on the
+/// CKB_TX_MESSAGE_ALL hash.
+///
+/// Signatures must be ordered the same as their public keys in the config,
+/// each public key can sign at most once, and the first R public keys must
+/// all sign.
+fn verify(message: &[u8; 32], lock: &[u8], args: &[u8]) -> Result<(), Error> {
+    if args.len() != PUBKEY_HASH_LENGTH {
+        return Err(Error::Verification(ERROR_INVALID_ARGUMENTS));
+    }
+    let (reserved, require_first_n, threshold, pubkeys) = match lock {
+        [s, r, m, n, ..] => (*s, *r as usize, *m as usize, *n as usize),
+        _ => return Err(Error::Verification(ERROR_INVALID_MULTISIG_CONFIG)),
+    };
+    if reserved != 0 || threshold == 0 || threshold > pubkeys || require_first_n > threshold {
+        return Err(Error::Verification(ERROR_INVALID_MULTISIG_CONFIG));
+    }
+    let config_length = 4 + pubkeys * PUBKEY_HASH_LENGTH;
+    if lock.len() < config_length {
+        return Err(Error::Verification(ERROR_INVALID_MULTISIG_CONFIG));
+    }
+    let (config, signatures) = lock.split_at(config_length);
+    if signatures.len() != threshold * SIGNATURE_LENGTH {
+        return Err(Error::Verification(ERROR_WRONG_SIGNATURE_COUNT));
+    }
+    if blake2b_256(config)[0..PUBKEY_HASH_LENGTH] != *args {
+        return Err(Error::Verification(ERROR_MULTISIG_HASH_MISMATCH));
+    }
+
+    let pubkey_hashes: Vec<&[u8]> = config[4..].chunks_exact(PUBKEY_HASH_LENGTH).collect();
+    let mut next_pubkey = 0;
+    let mut required_signed = 0;
+    for signature in signatures.chunks_exact(SIGNATURE_LENGTH) {
+        let signature = signature.try_into().expect("65-byte signature");
+        let pubkey_hash = recover_blake160(message, signature)
+            .map_err(|_| Error::Verification(ERROR_INVALID_SIGNATURE))?;
+        let index = (next_pubkey..pubkeys)
+            .find(|i| pubkey_hashes[*i] == pubkey_hash)
+            .ok_or(Error::Verification(ERROR_PUBKEY_HASH_MISMATCH))?;
+        if index < require_first_n {
+            required_signed += 1;
+        }
+        next_pubkey = index + 1;
+    }
+    if required_signed != require_first_n {
+        return Err(Error::Verification(ERROR_REQUIRED_SIGNER_MISSING));
+    }
+    Ok(())
+}
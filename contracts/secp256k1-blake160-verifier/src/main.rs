@@ -0,0 +1,62 @@
+#![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(any(feature = "native-simulator", test))]
+extern crate alloc;
+
+#[cfg(not(any(feature = "native-simulator", test)))]
+ckb_std::entry!(program_entry);
+#[cfg(not(any(feature = "native-simulator", test)))]
+ckb_std::default_alloc!();
+
+use alloc::vec::Vec;
+use ckb_std::env;
+use ckb_tx_message_all_utils::{
+    secp256k1_blake160::{verify_blake160, PUBKEY_HASH_LENGTH},
+    verifier::{decode_verifier_argv, VerifierArgs},
+};
+use core::ffi::CStr;
+
+/// Signature algorithm id for secp256k1 recoverable signatures, where the
+/// public key hash is the first 20 bytes of the ckb flavored blake2b hash
+/// of the compressed public key.
+pub const SECP256K1_BLAKE160_ALGORITHM_ID: u8 = 0;
+
+// Codes 1, 3 & 4 are shared with secp256k1-lock-ckb-tx-message-all
+pub use ckb_tx_message_all_utils::secp256k1_blake160::{
+    ERROR_INVALID_ARGUMENTS, ERROR_INVALID_SIGNATURE, ERROR_PUBKEY_HASH_MISMATCH,
+};
+pub const ERROR_UNKNOWN_ALGORITHM: i8 = 2;
+
+pub fn program_entry() -> i8 {
+    let argv: Vec<&CStr> = env::argv().iter().map(|arg| &**arg).collect();
+    let args = match decode_verifier_argv(&argv) {
+        Ok(args) => args,
+        Err(e) => {
+            ckb_std::debug!("Decode verifier arguments encounters error: {:?}", e);
+            return ERROR_INVALID_ARGUMENTS;
+        }
+    };
+    if args.algorithm_id != SECP256K1_BLAKE160_ALGORITHM_ID {
+        return ERROR_UNKNOWN_ALGORITHM;
+    }
+
+    match verify_secp256k1_blake160(&args) {
+        Ok(()) => 0,
+        Err(error_code) => error_code,
+    }
+}
+
+/// The signature takes 65 bytes: 64 bytes of r & s, followed by 1 byte of
+/// recovery id.
+fn verify_secp256k1_blake160(args: &VerifierArgs) -> Result<(), i8> {
+    let signature = args
+        .signature
+        .as_slice()
+        .try_into()
+        .map_err(|_| ERROR_INVALID_ARGUMENTS)?;
+    if args.pubkey_hash.len() != PUBKEY_HASH_LENGTH {
+        return Err(ERROR_INVALID_ARGUMENTS);
+    }
+    verify_blake160(&args.message, signature, &args.pubkey_hash).map_err(|e| e.exit_code())
+}
@@ -0,0 +1,41 @@
+#![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(any(feature = "native-simulator", test))]
+extern crate alloc;
+
+use ckb_tx_message_all_lock::{ckb_tx_message_all_lock, Error, VerificationCode};
+use ckb_tx_message_all_utils::secp256k1_blake160::{
+    self, verify_blake160, Secp256k1Error, PUBKEY_HASH_LENGTH,
+};
+
+// Exit codes follow secp256k1-blake160-verifier, so the same failure is
+// reported with the same code whether the signature is verified in place
+// or via delegation.
+pub const ERROR_INVALID_ARGUMENTS: VerificationCode =
+    VerificationCode::new(secp256k1_blake160::ERROR_INVALID_ARGUMENTS);
+pub const ERROR_INVALID_SIGNATURE: VerificationCode =
+    VerificationCode::new(secp256k1_blake160::ERROR_INVALID_SIGNATURE);
+pub const ERROR_PUBKEY_HASH_MISMATCH: VerificationCode =
+    VerificationCode::new(secp256k1_blake160::ERROR_PUBKEY_HASH_MISMATCH);
+
+ckb_tx_message_all_lock!(verify);
+
+/// Script args contain the public key hash: the first 20 bytes of the ckb
+/// flavored blake2b hash of the compressed public key. The lock field
+/// contains a 65-byte secp256k1 recoverable signature on CKB_TX_MESSAGE_ALL
+/// hash: 64 bytes of r & s, followed by 1 byte of recovery id.
+fn verify(message: &[u8; 32], lock: &[u8], args: &[u8]) -> Result<(), Error> {
+    if args.len() != PUBKEY_HASH_LENGTH {
+        return Err(Error::Verification(ERROR_INVALID_ARGUMENTS));
+    }
+    let signature = lock
+        .try_into()
+        .map_err(|_| Error::Verification(ERROR_INVALID_SIGNATURE))?;
+    verify_blake160(message, signature, args).map_err(|e| {
+        Error::Verification(match e {
+            Secp256k1Error::InvalidSignature => ERROR_INVALID_SIGNATURE,
+            Secp256k1Error::PubkeyHashMismatch => ERROR_PUBKEY_HASH_MISMATCH,
+        })
+    })
+}
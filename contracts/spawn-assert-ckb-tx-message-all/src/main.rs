@@ -0,0 +1,76 @@
+#![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(any(feature = "native-simulator", test))]
+extern crate alloc;
+
+#[cfg(not(any(feature = "native-simulator", test)))]
+ckb_std::entry!(program_entry);
+#[cfg(not(any(feature = "native-simulator", test)))]
+ckb_std::default_alloc!(16384, 2097152, 64);
+
+use ckb_gen_types::{packed::WitnessArgsReader, prelude::*};
+use ckb_std::{ckb_constants::Source, ckb_types::core::ScriptHashType, high_level};
+use ckb_tx_message_all_utils::{
+    ckb_tx_message_all_in_ckb_vm::ScriptGroup,
+    ckb_tx_message_all_oracle::spawn_ckb_tx_message_all_oracle, exit_code::ExitCode,
+    hasher::HashAlgorithm,
+};
+
+pub fn program_entry() -> i8 {
+    match run() {
+        Ok(()) => 0,
+        Err(code) => {
+            ckb_std::debug!("Spawn assert fails: {:?}", code);
+            code.into()
+        }
+    }
+}
+
+fn run() -> Result<(), ExitCode> {
+    // Script args contain the data hash of the oracle script(32 bytes), the
+    // hash algorithm id(1 byte), and an optional flag(1 byte). When the flag
+    // is 1, the script group is named via current lock script hash, instead
+    // of relying on the group shared with the oracle. Any other layout, or
+    // a flag other than 0 & 1, is rejected with ExitCode::InvalidArgs.
+    let script = high_level::load_script().map_err(|_| ExitCode::Syscall)?;
+    let args = script.args();
+    let args = args.as_reader().raw_data();
+    if args.len() != 33 && args.len() != 34 {
+        return Err(ExitCode::InvalidArgs);
+    }
+    let algorithm =
+        HashAlgorithm::try_from(args[32]).map_err(|_| ExitCode::UnknownHashAlgorithm)?;
+    let group = match args.get(33) {
+        None | Some(0) => ScriptGroup::Current,
+        Some(1) => {
+            ScriptGroup::Lock(high_level::load_script_hash().map_err(|_| ExitCode::Syscall)?)
+        }
+        Some(_) => return Err(ExitCode::InvalidArgs),
+    };
+
+    let hash =
+        spawn_ckb_tx_message_all_oracle(&args[0..32], ScriptHashType::Data2, algorithm, &group)
+            .map_err(|e| {
+                ckb_std::debug!("CKB_TX_MESSAGE_ALL oracle encounters error: {:?}", e);
+                ExitCode::Generation
+            })?;
+
+    let first_witness_data =
+        high_level::load_witness(0, Source::GroupInput).map_err(|_| ExitCode::Syscall)?;
+    let first_witness = WitnessArgsReader::from_slice(&first_witness_data)
+        .map_err(|_| ExitCode::InvalidWitnessArgs)?;
+    let lock_data = first_witness
+        .lock()
+        .to_opt()
+        .ok_or(ExitCode::MissingLock)?
+        .raw_data();
+
+    if lock_data.len() != hash.len() {
+        return Err(ExitCode::WrongLockLength);
+    }
+    if lock_data != hash {
+        return Err(ExitCode::HashMismatch);
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+#![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(any(feature = "native-simulator", test))]
+extern crate alloc;
+
+#[cfg(not(any(feature = "native-simulator", test)))]
+ckb_std::entry!(program_entry);
+#[cfg(not(any(feature = "native-simulator", test)))]
+// A large heap is required if we want to load the witness as a whole
+ckb_std::default_alloc!(16384, 2097152, 64);
+
+use ckb_gen_types::{packed::WitnessArgsReader, prelude::*};
+use ckb_std::{ckb_constants::Source, high_level};
+use ckb_tx_message_all_utils::{
+    ckb_tx_message_all_in_ckb_vm::{
+        generate_ckb_tx_message_all_with_first_witness, CkbTxMessageAllError,
+        DEFAULT_LOAD_BUFFER_LENGTH,
+    },
+    exit_code::ExitCode,
+    hasher::{HashAlgorithm, Hasher},
+};
+
+pub fn program_entry() -> i8 {
+    match run() {
+        Ok(()) => 0,
+        Err(code) => {
+            ckb_std::debug!("Type assert fails: {:?}", code);
+            code.into()
+        }
+    }
+}
+
+fn run() -> Result<(), ExitCode> {
+    // Empty args denote CKB flavored blake2b, otherwise the first byte of
+    // args is the hash algorithm id.
+    let script = high_level::load_script().map_err(|_| ExitCode::Syscall)?;
+    let algorithm = match script.args().as_reader().raw_data().first() {
+        None => HashAlgorithm::CkbBlake2b,
+        Some(id) => HashAlgorithm::try_from(*id).map_err(|_| ExitCode::UnknownHashAlgorithm)?,
+    };
+
+    // Witnesses of the type script group share indices with its input cells,
+    // a group with only output cells has no witness to load.
+    let first_witness_data =
+        high_level::load_witness(0, Source::GroupInput).map_err(|_| ExitCode::Syscall)?;
+    let first_witness = WitnessArgsReader::from_slice(&first_witness_data)
+        .map_err(|_| ExitCode::InvalidWitnessArgs)?;
+    let lock_data = first_witness
+        .lock()
+        .to_opt()
+        .ok_or(ExitCode::MissingLock)?
+        .raw_data();
+
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = [0u8; DEFAULT_LOAD_BUFFER_LENGTH];
+    generate_ckb_tx_message_all_with_first_witness(&mut hasher, &mut buffer, first_witness)
+        .map_err(|e| match e {
+            CkbTxMessageAllError::Syscall(_) => ExitCode::Syscall,
+            _ => ExitCode::Generation,
+        })?;
+    let hash = hasher.hash();
+
+    if lock_data.len() != hash.len() {
+        return Err(ExitCode::WrongLockLength);
+    }
+    if lock_data != hash {
+        return Err(ExitCode::HashMismatch);
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+#ifndef CKB_TX_MESSAGE_ALL_RS_H_
+#define CKB_TX_MESSAGE_ALL_RS_H_
+
+/*
+ * Declarations of the C API exported by the ckb-tx-message-all-c static
+ * library, which wraps the Rust in-VM CKB_TX_MESSAGE_ALL generator. This is
+ * an alternative to ckb_tx_message_all.h, the two shall not be included in
+ * the same file.
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* Returned when the first witness of current script group is not WitnessArgs
+ */
+#define CKB_TX_MESSAGE_ALL_ERROR_INVALID_WITNESS_ARGS 97
+/* Returned on other failures feeding CKB_TX_MESSAGE_ALL into writer */
+#define CKB_TX_MESSAGE_ALL_ERROR_GENERATION 99
+
+/* Same as the writer in ckb_tx_message_all.h, a non-zero return value aborts
+ * the generation */
+typedef int (*ckb_tx_message_write_func_t)(const uint8_t* data, size_t length,
+                                           void* context);
+
+/*
+ * Generates CKB_TX_MESSAGE_ALL for current script group, feeding the preimage
+ * into writer. Returns 0 on success, otherwise one of the following:
+ *
+ * - a non-zero value returned by writer, as it is;
+ * - a syscall error code, see ckb_syscalls.h;
+ * - one of the CKB_TX_MESSAGE_ALL_ERROR_* codes above.
+ */
+int ckb_tx_message_all_generate(ckb_tx_message_write_func_t writer,
+                                void* context);
+
+#endif /* CKB_TX_MESSAGE_ALL_RS_H_ */
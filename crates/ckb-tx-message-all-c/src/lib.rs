@@ -0,0 +1,106 @@
+//! C API of the in-VM CKB_TX_MESSAGE_ALL generator. When built for
+//! riscv64imac-unknown-none-elf, this crate produces a static library that
+//! C scripts can link against, instead of including ckb_tx_message_all.h.
+//! See include/ckb_tx_message_all_rs.h for the declarations.
+#![cfg_attr(target_arch = "riscv64", no_std)]
+
+extern crate alloc;
+
+use ckb_rust_std::io;
+use ckb_std::error::SysError;
+use ckb_tx_message_all_utils::{
+    ckb_tx_message_all_in_ckb_vm::{generate_ckb_tx_message_all, CkbTxMessageAllError},
+    exit_code::ExitCode,
+};
+use core::ffi::c_void;
+
+// The first witness of current script group is loaded as a whole, the heap
+// is sized the same as the assert scripts.
+#[cfg(target_arch = "riscv64")]
+ckb_std::default_alloc!(16384, 2097152, 64);
+
+#[cfg(target_arch = "riscv64")]
+#[panic_handler]
+fn panic_handler(panic_info: &core::panic::PanicInfo) -> ! {
+    #[cfg(debug_assertions)]
+    ckb_std::syscalls::debug(alloc::format!("{}", panic_info));
+    ckb_std::syscalls::exit(-1)
+}
+
+/// Same as CKB_TX_MESSAGE_ALL_ERROR_INVALID_WITNESS_ARGS in the C header
+pub const ERROR_INVALID_WITNESS_ARGS: i32 = ExitCode::InvalidWitnessArgs as i32;
+/// Same as CKB_TX_MESSAGE_ALL_ERROR_GENERATION in the C header
+pub const ERROR_GENERATION: i32 = ExitCode::Generation as i32;
+
+/// Same as ckb_tx_message_write_func_t in ckb_tx_message_all.h
+pub type WriteFunc =
+    unsafe extern "C" fn(data: *const u8, length: usize, context: *mut c_void) -> i32;
+
+/// Generates CKB_TX_MESSAGE_ALL for current script group, feeding the
+/// preimage into +writer+. It mirrors ckb_tx_message_all_generate from
+/// ckb_tx_message_all.h. Returns 0 on success, a non-zero return value of
+/// +writer+ as it is, a syscall error code, or one of the error codes above.
+///
+/// # Safety
+///
+/// +writer+ must be a valid function, +context+ is passed to +writer+ as it
+/// is and is never accessed by this function.
+#[no_mangle]
+pub unsafe extern "C" fn ckb_tx_message_all_generate(
+    writer: WriteFunc,
+    context: *mut c_void,
+) -> i32 {
+    let mut writer = CWriter {
+        writer,
+        context,
+        error: 0,
+    };
+    match generate_ckb_tx_message_all(&mut writer) {
+        Ok(()) => 0,
+        Err(_) if writer.error != 0 => writer.error,
+        Err(CkbTxMessageAllError::Syscall(e)) => syscall_error_code(e),
+        Err(CkbTxMessageAllError::Witness(_)) => ERROR_INVALID_WITNESS_ARGS,
+        Err(CkbTxMessageAllError::Io(_))
+        | Err(CkbTxMessageAllError::UnsupportedSighashMode)
+        | Err(CkbTxMessageAllError::EmptyBuffer) => ERROR_GENERATION,
+    }
+}
+
+struct CWriter {
+    writer: WriteFunc,
+    context: *mut c_void,
+    // Non-zero value returned by writer, which is kept so it can be returned
+    // to the caller as it is
+    error: i32,
+}
+
+impl io::Write for CWriter {
+    fn write(&mut self, data: &[u8]) -> Result<usize, io::Error> {
+        let err = unsafe { (self.writer)(data.as_ptr(), data.len(), self.context) };
+        if err != 0 {
+            self.error = err;
+            return Err(io::ErrorKind::Other.into());
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+// Error codes follow ckb_syscalls.h
+fn syscall_error_code(e: SysError) -> i32 {
+    match e {
+        SysError::IndexOutOfBound => 1,
+        SysError::ItemMissing => 2,
+        SysError::LengthNotEnough(_) => 3,
+        SysError::Encoding => 4,
+        SysError::WaitFailure => 5,
+        SysError::InvalidFd => 6,
+        SysError::OtherEndClosed => 7,
+        SysError::MaxVmsSpawned => 8,
+        SysError::MaxFdsCreated => 9,
+        SysError::Unknown(code) => code as i32,
+    }
+}
@@ -0,0 +1,307 @@
+//! A skeleton for lock scripts built on CKB_TX_MESSAGE_ALL.
+//!
+//! The skeleton loads script args and the first witness of current script
+//! group, then calculates CKB_TX_MESSAGE_ALL reusing the already loaded
+//! witness. A lock script only needs to provide the verification logic:
+//!
+//! ```ignore
+//! #![cfg_attr(not(any(feature = "native-simulator", test)), no_std)]
+//! #![cfg_attr(not(test), no_main)]
+//!
+//! #[cfg(any(feature = "native-simulator", test))]
+//! extern crate alloc;
+//!
+//! use ckb_tx_message_all_lock::{ckb_tx_message_all_lock, Error};
+//!
+//! ckb_tx_message_all_lock!(verify);
+//!
+//! fn verify(message: &[u8; 32], lock: &[u8], args: &[u8]) -> Result<(), Error> {
+//!     ...
+//! }
+//! ```
+//!
+//! Failures are mapped to exit codes via [Error::exit_code], so all locks
+//! built on this crate report the same failures with the same codes. Lock
+//! specific failures use codes within 1 - 93, see [VerificationCode].
+#![cfg_attr(not(test), no_std)]
+
+use ckb_gen_types::{packed::WitnessArgsReader, prelude::*};
+use ckb_std::{ckb_constants::Source, error::SysError, high_level};
+use ckb_tx_message_all_utils::ckb_tx_message_all_in_ckb_vm::{
+    generate_ckb_tx_message_with_first_witness, CkbTxMessageAllError, DEFAULT_LOAD_BUFFER_LENGTH,
+};
+pub use ckb_tx_message_all_utils::exit_code::ExitCode;
+pub use ckb_tx_message_all_utils::hasher::{HashAlgorithm, Hasher};
+pub use ckb_tx_message_all_utils::sighash_mode::SighashMode;
+use core::num::NonZeroI8;
+
+// Used by ckb_tx_message_all_lock! so lock scripts need not pick a
+// matching ckb-std version themselves.
+#[doc(hidden)]
+pub use ckb_std;
+
+/// Exit code of [Error::UnknownSighashMode]
+pub const ERROR_UNKNOWN_SIGHASH_MODE: i8 = ExitCode::UnknownSighashMode as i8;
+/// Exit code of [Error::UnknownHashAlgorithm]
+pub const ERROR_UNKNOWN_HASH_ALGORITHM: i8 = ExitCode::UnknownHashAlgorithm as i8;
+/// Exit code of [Error::Syscall]
+pub const ERROR_SYSCALL: i8 = ExitCode::Syscall as i8;
+/// Exit code of [Error::InvalidWitnessArgs]
+pub const ERROR_INVALID_WITNESS_ARGS: i8 = ExitCode::InvalidWitnessArgs as i8;
+/// Exit code of [Error::MissingLock]
+pub const ERROR_MISSING_LOCK: i8 = ExitCode::MissingLock as i8;
+/// Exit code of [Error::Generation]
+pub const ERROR_GENERATION: i8 = ExitCode::Generation as i8;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Script args denote a sighash mode that is not supported
+    UnknownSighashMode,
+    /// Script args denote a hash algorithm that is not supported
+    UnknownHashAlgorithm,
+    /// A syscall fails
+    Syscall(SysError),
+    /// The first witness of current script group is not a valid WitnessArgs
+    InvalidWitnessArgs,
+    /// The first witness of current script group has no lock field
+    MissingLock,
+    /// CKB_TX_MESSAGE_ALL cannot be written into the hasher
+    Generation(CkbTxMessageAllError),
+    /// Lock specific verification failure, the code is used as exit code
+    /// as it is.
+    Verification(VerificationCode),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i8 {
+        match self {
+            Error::UnknownSighashMode => ERROR_UNKNOWN_SIGHASH_MODE,
+            Error::UnknownHashAlgorithm => ERROR_UNKNOWN_HASH_ALGORITHM,
+            Error::Syscall(_) => ERROR_SYSCALL,
+            Error::InvalidWitnessArgs => ERROR_INVALID_WITNESS_ARGS,
+            Error::MissingLock => ERROR_MISSING_LOCK,
+            Error::Generation(_) => ERROR_GENERATION,
+            Error::Verification(code) => code.get(),
+        }
+    }
+}
+
+/// Largest exit code available to [Error::Verification]
+pub const MAX_VERIFICATION_CODE: i8 = 93;
+
+/// Exit code of a lock specific verification failure, ranging from 1 to
+/// [MAX_VERIFICATION_CODE]. 0 denotes success, 94 - 99 are reserved by the
+/// errors of this crate, while negative codes are used by CKB-VM itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerificationCode(NonZeroI8);
+
+/// An exit code that cannot be used by [Error::Verification]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidVerificationCode(pub i8);
+
+impl VerificationCode {
+    /// Panics when +code+ is out of range, which fails the build when used
+    /// to define a constant:
+    ///
+    /// ```ignore
+    /// pub const ERROR_INVALID_ARGS: VerificationCode = VerificationCode::new(1);
+    /// ```
+    pub const fn new(code: i8) -> Self {
+        match NonZeroI8::new(code) {
+            Some(code) if code.get() > 0 && code.get() <= MAX_VERIFICATION_CODE => {
+                VerificationCode(code)
+            }
+            _ => panic!("verification code must be within 1 - 93"),
+        }
+    }
+
+    pub const fn get(self) -> i8 {
+        self.0.get()
+    }
+}
+
+impl TryFrom<i8> for VerificationCode {
+    type Error = InvalidVerificationCode;
+
+    fn try_from(code: i8) -> Result<Self, Self::Error> {
+        if (1..=MAX_VERIFICATION_CODE).contains(&code) {
+            Ok(VerificationCode::new(code))
+        } else {
+            Err(InvalidVerificationCode(code))
+        }
+    }
+}
+
+impl From<VerificationCode> for i8 {
+    fn from(code: VerificationCode) -> Self {
+        code.get()
+    }
+}
+
+impl From<SysError> for Error {
+    fn from(e: SysError) -> Self {
+        Error::Syscall(e)
+    }
+}
+
+impl From<CkbTxMessageAllError> for Error {
+    fn from(e: CkbTxMessageAllError) -> Self {
+        match e {
+            CkbTxMessageAllError::Syscall(e) => Error::Syscall(e),
+            CkbTxMessageAllError::Witness(_) => Error::InvalidWitnessArgs,
+            e => Error::Generation(e),
+        }
+    }
+}
+
+/// Decides the hash algorithm used to hash CKB_TX_MESSAGE_ALL. It is
+/// implemented by [HashAlgorithm] itself for a fixed algorithm, and by
+/// functions picking an algorithm from script args, such as
+/// [hash_algorithm_from_args].
+pub trait SelectHashAlgorithm {
+    fn select(self, args: &[u8]) -> Result<HashAlgorithm, Error>;
+}
+
+impl SelectHashAlgorithm for HashAlgorithm {
+    fn select(self, _args: &[u8]) -> Result<HashAlgorithm, Error> {
+        Ok(self)
+    }
+}
+
+impl<F: FnOnce(&[u8]) -> Result<HashAlgorithm, Error>> SelectHashAlgorithm for F {
+    fn select(self, args: &[u8]) -> Result<HashAlgorithm, Error> {
+        self(args)
+    }
+}
+
+/// Uses the first byte of script args as the hash algorithm id, empty
+/// args denote CKB flavored blake2b.
+pub fn hash_algorithm_from_args(args: &[u8]) -> Result<HashAlgorithm, Error> {
+    match args.first() {
+        None => Ok(HashAlgorithm::CkbBlake2b),
+        Some(id) => HashAlgorithm::try_from(*id).map_err(|_| Error::UnknownHashAlgorithm),
+    }
+}
+
+/// Decides the [SighashMode] of the message passed to the lock, in the same
+/// way as [SelectHashAlgorithm].
+pub trait SelectSighashMode {
+    fn select(self, args: &[u8]) -> Result<SighashMode, Error>;
+}
+
+impl SelectSighashMode for SighashMode {
+    fn select(self, _args: &[u8]) -> Result<SighashMode, Error> {
+        Ok(self)
+    }
+}
+
+impl<F: FnOnce(&[u8]) -> Result<SighashMode, Error>> SelectSighashMode for F {
+    fn select(self, args: &[u8]) -> Result<SighashMode, Error> {
+        self(args)
+    }
+}
+
+/// Uses the second byte of script args as the sighash mode id, args shorter
+/// than 2 bytes denote [SighashMode::All].
+pub fn sighash_mode_from_args(args: &[u8]) -> Result<SighashMode, Error> {
+    match args.get(1) {
+        None => Ok(SighashMode::All),
+        Some(id) => SighashMode::try_from(*id).map_err(|_| Error::UnknownSighashMode),
+    }
+}
+
+/// Runs the lock: +verify+ is called with CKB_TX_MESSAGE_ALL hashed via
+/// the algorithm picked by +algorithm+, the lock field of the first witness
+/// in current script group, and script args.
+pub fn run<F, S>(verify: F, algorithm: S) -> Result<(), Error>
+where
+    F: Fn(&[u8; 32], &[u8], &[u8]) -> Result<(), Error>,
+    S: SelectHashAlgorithm,
+{
+    run_with_sighash_mode(verify, algorithm, SighashMode::All)
+}
+
+/// Same as [run], except that +verify+ is called with the message of the
+/// sighash mode picked by +mode+.
+pub fn run_with_sighash_mode<F, S, M>(verify: F, algorithm: S, mode: M) -> Result<(), Error>
+where
+    F: Fn(&[u8; 32], &[u8], &[u8]) -> Result<(), Error>,
+    S: SelectHashAlgorithm,
+    M: SelectSighashMode,
+{
+    let script = high_level::load_script()?;
+    let args = script.args();
+    let algorithm = algorithm.select(args.as_reader().raw_data())?;
+    let mode = mode.select(args.as_reader().raw_data())?;
+
+    let first_witness_data = high_level::load_witness(0, Source::GroupInput)?;
+    let first_witness = WitnessArgsReader::from_slice(&first_witness_data)
+        .map_err(|_| Error::InvalidWitnessArgs)?;
+    let lock = first_witness.lock().to_opt().ok_or(Error::MissingLock)?;
+
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = [0u8; DEFAULT_LOAD_BUFFER_LENGTH];
+    generate_ckb_tx_message_with_first_witness(&mut hasher, &mut buffer, mode, first_witness)?;
+
+    verify(&hasher.hash(), lock.raw_data(), args.as_reader().raw_data())
+}
+
+/// Defines the entry of a CKB_TX_MESSAGE_ALL lock script, with +verify+
+/// being a function of signature
+/// `fn(message: &[u8; 32], lock: &[u8], args: &[u8]) -> Result<(), Error>`.
+/// CKB flavored blake2b is used to hash CKB_TX_MESSAGE_ALL, unless a
+/// different [HashAlgorithm], or a function picking one from script args
+/// (see [SelectHashAlgorithm]), is provided as the second argument. An
+/// optional third argument picks an experimental [SighashMode] in the same
+/// way(see [SelectSighashMode]).
+#[macro_export]
+macro_rules! ckb_tx_message_all_lock {
+    ($verify:path) => {
+        $crate::ckb_tx_message_all_lock!($verify, $crate::HashAlgorithm::CkbBlake2b);
+    };
+    ($verify:path, $algorithm:expr) => {
+        $crate::ckb_tx_message_all_lock!($verify, $algorithm, $crate::SighashMode::All);
+    };
+    ($verify:path, $algorithm:expr, $mode:expr) => {
+        #[cfg(not(any(feature = "native-simulator", test)))]
+        $crate::ckb_std::entry!(program_entry);
+        #[cfg(not(any(feature = "native-simulator", test)))]
+        // A large heap is required as the first witness is loaded as a whole
+        $crate::ckb_std::default_alloc!(16384, 2097152, 64);
+
+        pub fn program_entry() -> i8 {
+            match $crate::run_with_sighash_mode($verify, $algorithm, $mode) {
+                Ok(()) => 0,
+                Err(e) => {
+                    $crate::ckb_std::debug!("CKB_TX_MESSAGE_ALL lock fails: {:?}", e);
+                    e.exit_code()
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verification_code_range() {
+        for code in [i8::MIN, -1, 0, 94, 99, i8::MAX] {
+            assert_eq!(
+                VerificationCode::try_from(code),
+                Err(InvalidVerificationCode(code))
+            );
+        }
+        for code in [1, 2, MAX_VERIFICATION_CODE] {
+            let error = Error::Verification(VerificationCode::try_from(code).unwrap());
+            assert_eq!(error.exit_code(), code);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_verification_code_rejects_success() {
+        VerificationCode::new(0);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{message_version::MessageVersion, sighash_mode::SighashMode};
 use ckb_gen_types::{
     bytes::Bytes,
     packed::{CellOutput, Script, Transaction, WitnessArgsReader},
@@ -8,14 +9,31 @@ use molecule::error::VerificationError;
 use std::io;
 
 #[derive(Debug)]
-#[allow(dead_code)]
 pub enum CkbTxMessageAllError {
     InvalidMockTx,
     UnknownScriptGroup,
+    /// The first input cell of current script group has no witness
+    MissingWitness,
+    /// Sighash modes other than All only support lock script groups
+    UnsupportedSighashMode,
     Witness(VerificationError),
     Io(io::Error),
 }
 
+impl CkbTxMessageAllError {
+    /// A stable identifier of the error, which is kept in test vectors
+    pub fn reason(&self) -> &'static str {
+        match self {
+            CkbTxMessageAllError::InvalidMockTx => "invalid_mock_tx",
+            CkbTxMessageAllError::UnknownScriptGroup => "unknown_script_group",
+            CkbTxMessageAllError::MissingWitness => "missing_witness",
+            CkbTxMessageAllError::UnsupportedSighashMode => "unsupported_sighash_mode",
+            CkbTxMessageAllError::Witness(_) => "invalid_witness_args",
+            CkbTxMessageAllError::Io(_) => "io",
+        }
+    }
+}
+
 impl From<VerificationError> for CkbTxMessageAllError {
     fn from(e: VerificationError) -> Self {
         CkbTxMessageAllError::Witness(e)
@@ -30,17 +48,38 @@ impl From<io::Error> for CkbTxMessageAllError {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ScriptOrIndex {
+    /// Lock script group of input cells using the script
     Script(Script),
+    /// Lock script group of the input cell at the index
     Index(usize),
+    /// Type script group of input cells using the script as type script.
+    /// Output cells of the group are not involved, a group with no input
+    /// cells results in [CkbTxMessageAllError::UnknownScriptGroup].
+    TypeScript(Script),
 }
 
 pub fn generate_ckb_tx_message_all_from_mock_tx<W: io::Write>(
     mock_tx: &MockTransaction,
     script_or_index: ScriptOrIndex,
     writer: &mut W,
+) -> Result<(), CkbTxMessageAllError> {
+    generate_ckb_tx_message_all_from_mock_tx_with_version(
+        mock_tx,
+        script_or_index,
+        MessageVersion::V1,
+        writer,
+    )
+}
+
+/// Generates CKB_TX_MESSAGE_ALL following the specified revision of the spec
+pub fn generate_ckb_tx_message_all_from_mock_tx_with_version<W: io::Write>(
+    mock_tx: &MockTransaction,
+    script_or_index: ScriptOrIndex,
+    version: MessageVersion,
+    writer: &mut W,
 ) -> Result<(), CkbTxMessageAllError> {
     let inputs = locate_inputs(mock_tx)?;
-    generate_ckb_tx_message_all(&mock_tx.tx, &inputs, script_or_index, writer)
+    generate_ckb_tx_message_all_with_version(&mock_tx.tx, &inputs, script_or_index, version, writer)
 }
 
 pub fn generate_ckb_tx_message_all<W: io::Write>(
@@ -48,6 +87,22 @@ pub fn generate_ckb_tx_message_all<W: io::Write>(
     inputs: &[(CellOutput, Bytes)],
     script_or_index: ScriptOrIndex,
     writer: &mut W,
+) -> Result<(), CkbTxMessageAllError> {
+    generate_ckb_tx_message_all_with_version(
+        tx,
+        inputs,
+        script_or_index,
+        MessageVersion::V1,
+        writer,
+    )
+}
+
+pub fn generate_ckb_tx_message_all_with_version<W: io::Write>(
+    tx: &Transaction,
+    inputs: &[(CellOutput, Bytes)],
+    script_or_index: ScriptOrIndex,
+    version: MessageVersion,
+    writer: &mut W,
 ) -> Result<(), CkbTxMessageAllError> {
     assert_eq!(tx.raw().inputs().len(), inputs.len());
     let script_group_indices = find_script_group(inputs, script_or_index)?;
@@ -56,7 +111,7 @@ pub fn generate_ckb_tx_message_all<W: io::Write>(
     let first_witness_content = tx
         .witnesses()
         .get(script_group_indices[0])
-        .ok_or(CkbTxMessageAllError::InvalidMockTx)?
+        .ok_or(CkbTxMessageAllError::MissingWitness)?
         .raw_data();
     let first_witness = WitnessArgsReader::from_slice(&first_witness_content)?;
 
@@ -71,20 +126,14 @@ pub fn generate_ckb_tx_message_all<W: io::Write>(
         writer.write_all(data)?;
     }
 
-    // Hash the first witness of current script group
-    write_length(first_witness.input_type().as_slice().len(), writer)?;
-    writer.write_all(first_witness.input_type().as_slice())?;
-    write_length(first_witness.output_type().as_slice().len(), writer)?;
-    writer.write_all(first_witness.output_type().as_slice())?;
-
-    // Hash the remaining witnesses in current script group
-    for i in script_group_indices.iter().skip(1) {
-        if let Some(witness) = tx.witnesses().get(*i).map(|w| w.raw_data()) {
-            write_length(witness.len(), writer)?;
-            writer.write_all(&witness)?;
-        }
+    // Revisions after V1 hash additional fields(e.g., contents of cell deps)
+    // here, keep the in-VM generator in sync.
+    match version {
+        MessageVersion::V1 => (),
     }
 
+    write_group_witnesses(tx, &first_witness, &script_group_indices, writer)?;
+
     // Hash witnesses that do not have input cells of the same indices
     for witness in tx
         .witnesses()
@@ -100,7 +149,110 @@ pub fn generate_ckb_tx_message_all<W: io::Write>(
     Ok(())
 }
 
-fn locate_inputs(
+/// Generates the message of the specified [SighashMode], see the mode for
+/// its preimage. [SighashMode::All] generates CKB_TX_MESSAGE_ALL.
+pub fn generate_ckb_tx_message_from_mock_tx<W: io::Write>(
+    mock_tx: &MockTransaction,
+    script_or_index: ScriptOrIndex,
+    mode: SighashMode,
+    writer: &mut W,
+) -> Result<(), CkbTxMessageAllError> {
+    let inputs = locate_inputs(mock_tx)?;
+    generate_ckb_tx_message(&mock_tx.tx, &inputs, script_or_index, mode, writer)
+}
+
+pub fn generate_ckb_tx_message<W: io::Write>(
+    tx: &Transaction,
+    inputs: &[(CellOutput, Bytes)],
+    script_or_index: ScriptOrIndex,
+    mode: SighashMode,
+    writer: &mut W,
+) -> Result<(), CkbTxMessageAllError> {
+    match (mode, &script_or_index) {
+        (SighashMode::All, _) => {
+            return generate_ckb_tx_message_all(tx, inputs, script_or_index, writer);
+        }
+        (_, ScriptOrIndex::TypeScript(_)) => {
+            return Err(CkbTxMessageAllError::UnsupportedSighashMode);
+        }
+        _ => (),
+    }
+    assert_eq!(tx.raw().inputs().len(), inputs.len());
+    let script_group_indices = find_script_group(inputs, script_or_index)?;
+
+    let first_witness_content = tx
+        .witnesses()
+        .get(script_group_indices[0])
+        .ok_or(CkbTxMessageAllError::MissingWitness)?
+        .raw_data();
+    let first_witness = WitnessArgsReader::from_slice(&first_witness_content)?;
+
+    writer.write_all(&[mode.into()])?;
+
+    // Hash input cells of current script group
+    write_length(script_group_indices.len(), writer)?;
+    for i in &script_group_indices {
+        let (cell_output, data) = &inputs[*i];
+        writer.write_all(tx.raw().inputs().get(*i).expect("input").as_slice())?;
+        writer.write_all(cell_output.as_slice())?;
+        write_length(data.len(), writer)?;
+        writer.write_all(data)?;
+    }
+
+    // Hash output cells committed by the mode
+    let outputs = mode.committed_outputs(&script_group_indices, tx.raw().outputs().len());
+    write_length(outputs.len(), writer)?;
+    for i in outputs {
+        let cell_output = tx.raw().outputs().get(i).expect("output");
+        let data = tx
+            .raw()
+            .outputs_data()
+            .get(i)
+            .ok_or(CkbTxMessageAllError::InvalidMockTx)?
+            .raw_data();
+        writer.write_all(cell_output.as_slice())?;
+        write_length(data.len(), writer)?;
+        writer.write_all(&data)?;
+    }
+
+    write_group_witnesses(tx, &first_witness, &script_group_indices, writer)?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+// Hashes witnesses of current script group, the first one being already
+// validated as WitnessArgs
+fn write_group_witnesses<W: io::Write>(
+    tx: &Transaction,
+    first_witness: &WitnessArgsReader,
+    script_group_indices: &[usize],
+    writer: &mut W,
+) -> Result<(), CkbTxMessageAllError> {
+    // Hash the first witness of current script group
+    write_length(first_witness.input_type().as_slice().len(), writer)?;
+    writer.write_all(first_witness.input_type().as_slice())?;
+    write_length(first_witness.output_type().as_slice().len(), writer)?;
+    writer.write_all(first_witness.output_type().as_slice())?;
+
+    // Hash the remaining witnesses in current script group. Later input
+    // cells of the group might have no witness, hashing stops at the first
+    // one lacking a witness, same as in-VM generators stopping at
+    // IndexOutOfBound. Indices are increasing, so all the following input
+    // cells lack a witness as well.
+    for witness in script_group_indices
+        .iter()
+        .skip(1)
+        .map_while(|i| tx.witnesses().get(*i))
+    {
+        let witness = witness.raw_data();
+        write_length(witness.len(), writer)?;
+        writer.write_all(&witness)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn locate_inputs(
     mock_tx: &MockTransaction,
 ) -> Result<Vec<(CellOutput, Bytes)>, CkbTxMessageAllError> {
     let mut result = Vec::with_capacity(mock_tx.tx.raw().inputs().len());
@@ -120,18 +272,28 @@ fn find_script_group(
     inputs: &[(CellOutput, Bytes)],
     script_or_index: ScriptOrIndex,
 ) -> Result<Vec<usize>, CkbTxMessageAllError> {
-    let script = match script_or_index {
-        ScriptOrIndex::Script(script) => script,
-        ScriptOrIndex::Index(i) => inputs
-            .get(i)
-            .ok_or(CkbTxMessageAllError::UnknownScriptGroup)?
-            .0
-            .lock(),
+    let (script, type_script) = match script_or_index {
+        ScriptOrIndex::Script(script) => (script, false),
+        ScriptOrIndex::Index(i) => (
+            inputs
+                .get(i)
+                .ok_or(CkbTxMessageAllError::UnknownScriptGroup)?
+                .0
+                .lock(),
+            false,
+        ),
+        ScriptOrIndex::TypeScript(script) => (script, true),
     };
     let indices: Vec<_> = inputs
         .iter()
         .enumerate()
-        .filter(|(_i, (cell_output, _data))| cell_output.lock() == script)
+        .filter(|(_i, (cell_output, _data))| {
+            if type_script {
+                cell_output.type_().to_opt().as_ref() == Some(&script)
+            } else {
+                cell_output.lock() == script
+            }
+        })
         .map(|(i, _)| i)
         .collect();
     if indices.is_empty() {
@@ -1,3 +1,5 @@
+use crate::{message_version::MessageVersion, sighash_mode::SighashMode};
+use alloc::vec::Vec;
 use ckb_gen_types::{packed::WitnessArgsReader, prelude::*};
 use ckb_rust_std::io;
 use ckb_std::{
@@ -6,14 +8,19 @@ use ckb_std::{
     high_level::{self, QueryIter},
     syscalls,
 };
+#[cfg(feature = "trace")]
+use io::Write as _;
 use molecule::error::VerificationError;
 
 #[derive(Debug)]
-#[allow(dead_code)]
 pub enum CkbTxMessageAllError {
     Witness(VerificationError),
     Syscall(SysError),
     Io(io::Error),
+    /// Sighash modes other than All only support lock script groups
+    UnsupportedSighashMode,
+    /// The load buffer passed by the caller is empty
+    EmptyBuffer,
 }
 
 impl From<VerificationError> for CkbTxMessageAllError {
@@ -34,9 +41,165 @@ impl From<io::Error> for CkbTxMessageAllError {
     }
 }
 
+// Emits the boundary of the preimage segment that was just written, when the
+// trace feature is enabled. It compiles to nothing otherwise.
+macro_rules! trace_segment {
+    ($writer:expr, $($args:tt)+) => {
+        #[cfg(feature = "trace")]
+        $writer.end_segment(format_args!($($args)+));
+    };
+}
+
+/// Script group for which CKB_TX_MESSAGE_ALL is generated
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptGroup {
+    /// The script group of currently running script, which can either be a
+    /// lock script group or a type script group. Its witnesses are those
+    /// sharing indices with input cells of the group, a type script group
+    /// with no input cells(e.g., when cells are created) thus fails with
+    /// [SysError::IndexOutOfBound].
+    Current,
+    /// The lock script group of input cells using a lock script with
+    /// the specified hash, it does not need to be the running script
+    Lock([u8; 32]),
+}
+
+/// Prefix of debug messages carrying a chunk of the preimage in hex notation,
+/// as emitted by dump-ckb-tx-message-all-preimage contract.
+pub const DUMPED_PREIMAGE_PREFIX: &str = "CKB_TX_MESSAGE_ALL preimage: ";
+/// Prefix of the debug message carrying the hash of the dumped preimage in
+/// hex notation.
+pub const DUMPED_HASH_PREFIX: &str = "CKB_TX_MESSAGE_ALL hash: ";
+/// Prefix of debug messages emitted by the trace feature, each one reads
+/// `<prefix><label>: offset <offset>, length <length>`.
+pub const TRACE_SEGMENT_PREFIX: &str = "CKB_TX_MESSAGE_ALL segment ";
+
+/// Length of the load buffer used by [generate_ckb_tx_message_all].
+pub const DEFAULT_LOAD_BUFFER_LENGTH: usize = 32 * 1024;
+
 pub fn generate_ckb_tx_message_all<W: io::Write>(
     writer: &mut W,
 ) -> Result<(), CkbTxMessageAllError> {
+    let mut buffer = [0u8; DEFAULT_LOAD_BUFFER_LENGTH];
+    generate_ckb_tx_message_all_with_buffer(writer, &mut buffer)
+}
+
+/// Generates CKB_TX_MESSAGE_ALL using +buffer+ as the only scratch space for
+/// streaming cell outputs, cell data and witnesses. A larger buffer means fewer
+/// syscalls at the expense of more memory, callers are free to pick a length
+/// that suits their script, either on the stack or from the heap. An empty
+/// buffer results in [CkbTxMessageAllError::EmptyBuffer].
+pub fn generate_ckb_tx_message_all_with_buffer<W: io::Write>(
+    writer: &mut W,
+    buffer: &mut [u8],
+) -> Result<(), CkbTxMessageAllError> {
+    generate_ckb_tx_message_all_for_group(writer, buffer, &ScriptGroup::Current)
+}
+
+/// Generates CKB_TX_MESSAGE_ALL for the specified script group, using +buffer+
+/// as the only scratch space.
+pub fn generate_ckb_tx_message_all_for_group<W: io::Write>(
+    writer: &mut W,
+    buffer: &mut [u8],
+    group: &ScriptGroup,
+) -> Result<(), CkbTxMessageAllError> {
+    generate_ckb_tx_message_all_with_version(writer, buffer, group, MessageVersion::V1)
+}
+
+/// Generates CKB_TX_MESSAGE_ALL for the specified script group following the
+/// specified revision of the spec, using +buffer+ as the only scratch space.
+pub fn generate_ckb_tx_message_all_with_version<W: io::Write>(
+    writer: &mut W,
+    buffer: &mut [u8],
+    group: &ScriptGroup,
+    version: MessageVersion,
+) -> Result<(), CkbTxMessageAllError> {
+    generate(writer, buffer, group, version, None)
+}
+
+/// Generates CKB_TX_MESSAGE_ALL for current script group, reusing
+/// +first_witness+, which the caller has already loaded & validated as the
+/// first witness of current script group. Scripts that need other fields of
+/// the first witness(e.g., lock) can thus avoid loading it a second time.
+pub fn generate_ckb_tx_message_all_with_first_witness<W: io::Write>(
+    writer: &mut W,
+    buffer: &mut [u8],
+    first_witness: WitnessArgsReader,
+) -> Result<(), CkbTxMessageAllError> {
+    generate(
+        writer,
+        buffer,
+        &ScriptGroup::Current,
+        MessageVersion::V1,
+        Some(first_witness),
+    )
+}
+
+/// Generates the message of the specified [SighashMode] for the specified
+/// script group, using +buffer+ as the only scratch space. See the mode for
+/// its preimage, [SighashMode::All] generates CKB_TX_MESSAGE_ALL.
+pub fn generate_ckb_tx_message<W: io::Write>(
+    writer: &mut W,
+    buffer: &mut [u8],
+    group: &ScriptGroup,
+    mode: SighashMode,
+) -> Result<(), CkbTxMessageAllError> {
+    match mode {
+        SighashMode::All => generate(writer, buffer, group, MessageVersion::V1, None),
+        _ => generate_partial(writer, buffer, group, mode, None),
+    }
+}
+
+/// Same as [generate_ckb_tx_message_all_with_first_witness], generating the
+/// message of the specified [SighashMode] instead.
+pub fn generate_ckb_tx_message_with_first_witness<W: io::Write>(
+    writer: &mut W,
+    buffer: &mut [u8],
+    mode: SighashMode,
+    first_witness: WitnessArgsReader,
+) -> Result<(), CkbTxMessageAllError> {
+    match mode {
+        SighashMode::All => generate(
+            writer,
+            buffer,
+            &ScriptGroup::Current,
+            MessageVersion::V1,
+            Some(first_witness),
+        ),
+        _ => generate_partial(
+            writer,
+            buffer,
+            &ScriptGroup::Current,
+            mode,
+            Some(first_witness),
+        ),
+    }
+}
+
+fn generate<W: io::Write>(
+    writer: &mut W,
+    buffer: &mut [u8],
+    group: &ScriptGroup,
+    version: MessageVersion,
+    first_witness: Option<WitnessArgsReader>,
+) -> Result<(), CkbTxMessageAllError> {
+    if buffer.is_empty() {
+        return Err(CkbTxMessageAllError::EmptyBuffer);
+    }
+    #[cfg(feature = "trace")]
+    let writer = &mut TraceWriter::new(writer);
+
+    // Witnesses of current script group can be loaded via GroupInput source,
+    // for other groups we locate the input cells of the group first.
+    let group_indices = match group {
+        ScriptGroup::Current => None,
+        ScriptGroup::Lock(lock_hash) => Some(lock_group_indices(lock_hash)),
+    };
+    let witness_location = |n: usize| match &group_indices {
+        None => Some((n, Source::GroupInput)),
+        Some(indices) => indices.get(n).map(|index| (*index, Source::Input)),
+    };
+
     // NOTE: while the first step in CKB_TX_MESSAGE_ALL's specification is to validate
     // the format of the first witness in current script group, the actual validation
     // code is shifted to a later stage due to certain reasons we will explain later.
@@ -45,24 +208,37 @@ pub fn generate_ckb_tx_message_all<W: io::Write>(
 
     // Hash tx hash
     writer.write_all(&high_level::load_tx_hash()?)?;
+    trace_segment!(writer, "tx hash");
 
     // Hash contents of all input cells
-    let cell_output_iter = QueryIter::new(
-        |index, source| load_initial(syscalls::load_cell, index, source),
-        Source::Input,
-    );
-    let cell_data_iter = QueryIter::new(
-        |index, source| load_initial(syscalls::load_cell_data, index, source),
-        Source::Input,
-    );
     let mut input_cell_count = 0;
-    for (initial_cell_output, initial_cell_data) in cell_output_iter.zip(cell_data_iter) {
+    while load_and_hash(
+        syscalls::load_cell,
+        input_cell_count,
+        Source::Input,
+        false,
+        buffer,
+        writer,
+    )? {
+        trace_segment!(writer, "input cell {} output", input_cell_count);
+        if !load_and_hash(
+            syscalls::load_cell_data,
+            input_cell_count,
+            Source::Input,
+            true,
+            buffer,
+            writer,
+        )? {
+            return Err(SysError::IndexOutOfBound.into());
+        }
+        trace_segment!(writer, "input cell {} data", input_cell_count);
         input_cell_count += 1;
+    }
 
-        load_and_hash(initial_cell_output, syscalls::load_cell, writer)?;
-
-        write_length(initial_cell_data.full_length, writer)?;
-        load_and_hash(initial_cell_data, syscalls::load_cell_data, writer)?;
+    // Revisions after V1 hash additional fields(e.g., contents of cell deps)
+    // here, keep the off-chain generator in sync.
+    match version {
+        MessageVersion::V1 => (),
     }
 
     // Hash the first witness of current script group
@@ -94,107 +270,254 @@ pub fn generate_ckb_tx_message_all<W: io::Write>(
         // peek into internal data structure of the lazy reader API(e.g., we need to
         // know the length of a cursor structure). It remains a debate which solution
         // is a more proper one.
-        let first_witness_data = high_level::load_witness(0, Source::GroupInput)?;
-        let first_witness = WitnessArgsReader::from_slice(&first_witness_data)?;
+        let first_witness_data;
+        let first_witness = match first_witness {
+            Some(first_witness) => first_witness,
+            None => {
+                let (index, source) = witness_location(0).ok_or(SysError::IndexOutOfBound)?;
+                first_witness_data = high_level::load_witness(index, source)?;
+                WitnessArgsReader::from_slice(&first_witness_data)?
+            }
+        };
 
         write_length(first_witness.input_type().as_slice().len(), writer)?;
         writer.write_all(first_witness.input_type().as_slice())?;
+        trace_segment!(writer, "first group witness input_type");
         write_length(first_witness.output_type().as_slice().len(), writer)?;
         writer.write_all(first_witness.output_type().as_slice())?;
+        trace_segment!(writer, "first group witness output_type");
     }
 
     // Hash the remaining witnesses in current script group
-    for initial_witness in QueryIter::new(
-        |index, source| load_initial(syscalls::load_witness, index, source),
-        Source::GroupInput,
-    )
-    .skip(1)
-    {
-        write_length(initial_witness.full_length, writer)?;
-        load_and_hash(initial_witness, syscalls::load_witness, writer)?;
+    let mut n = 1;
+    while let Some((index, source)) = witness_location(n) {
+        if !load_and_hash(syscalls::load_witness, index, source, true, buffer, writer)? {
+            break;
+        }
+        trace_segment!(writer, "group witness {}", n);
+        n += 1;
     }
 
     // Hash witnesses which do not have input cells of matching indices
-    for initial_witness in QueryIter::new(
-        |index, source| load_initial(syscalls::load_witness, index, source),
+    let mut index = input_cell_count;
+    while load_and_hash(
+        syscalls::load_witness,
+        index,
         Source::Input,
-    )
-    .skip(input_cell_count)
+        true,
+        buffer,
+        writer,
+    )? {
+        trace_segment!(writer, "witness {}", index);
+        index += 1;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+// Generates the message of sighash modes other than All, which only support
+// lock script groups.
+fn generate_partial<W: io::Write>(
+    writer: &mut W,
+    buffer: &mut [u8],
+    group: &ScriptGroup,
+    mode: SighashMode,
+    first_witness: Option<WitnessArgsReader>,
+) -> Result<(), CkbTxMessageAllError> {
+    if buffer.is_empty() {
+        return Err(CkbTxMessageAllError::EmptyBuffer);
+    }
+    #[cfg(feature = "trace")]
+    let writer = &mut TraceWriter::new(writer);
+
+    let lock_hash = match group {
+        ScriptGroup::Current => high_level::load_script_hash()?,
+        ScriptGroup::Lock(lock_hash) => *lock_hash,
+    };
+    let group_indices = lock_group_indices(&lock_hash);
+    // No input cell uses current script as lock, it runs as a type script
+    if group_indices.is_empty() {
+        return Err(CkbTxMessageAllError::UnsupportedSighashMode);
+    }
+
+    writer.write_all(&[mode.into()])?;
+    trace_segment!(writer, "sighash mode");
+
+    // Hash input cells of current script group
+    write_length(group_indices.len(), writer)?;
+    for index in &group_indices {
+        for (load_fn, with_length) in [
+            (syscalls::load_input as LoadFn, false),
+            (syscalls::load_cell, false),
+            (syscalls::load_cell_data, true),
+        ] {
+            if !load_and_hash(load_fn, *index, Source::Input, with_length, buffer, writer)? {
+                return Err(SysError::IndexOutOfBound.into());
+            }
+        }
+        trace_segment!(writer, "group input cell {}", index);
+    }
+
+    // Hash output cells committed by the mode
+    let output_count = QueryIter::new(high_level::load_cell_capacity, Source::Output).count();
+    let outputs = mode.committed_outputs(&group_indices, output_count);
+    write_length(outputs.len(), writer)?;
+    for index in outputs {
+        for (load_fn, with_length) in [
+            (syscalls::load_cell as LoadFn, false),
+            (syscalls::load_cell_data, true),
+        ] {
+            if !load_and_hash(load_fn, index, Source::Output, with_length, buffer, writer)? {
+                return Err(SysError::IndexOutOfBound.into());
+            }
+        }
+        trace_segment!(writer, "output cell {}", index);
+    }
+
+    // Hash the first witness of current script group, see generate for why
+    // it is loaded as a whole
     {
-        write_length(initial_witness.full_length, writer)?;
-        load_and_hash(initial_witness, syscalls::load_witness, writer)?;
+        let first_witness_data;
+        let first_witness = match first_witness {
+            Some(first_witness) => first_witness,
+            None => {
+                first_witness_data = high_level::load_witness(group_indices[0], Source::Input)?;
+                WitnessArgsReader::from_slice(&first_witness_data)?
+            }
+        };
+
+        write_length(first_witness.input_type().as_slice().len(), writer)?;
+        writer.write_all(first_witness.input_type().as_slice())?;
+        trace_segment!(writer, "first group witness input_type");
+        write_length(first_witness.output_type().as_slice().len(), writer)?;
+        writer.write_all(first_witness.output_type().as_slice())?;
+        trace_segment!(writer, "first group witness output_type");
+    }
+
+    // Hash the remaining witnesses in current script group
+    for index in group_indices.iter().skip(1) {
+        if !load_and_hash(
+            syscalls::load_witness,
+            *index,
+            Source::Input,
+            true,
+            buffer,
+            writer,
+        )? {
+            break;
+        }
+        trace_segment!(writer, "witness {}", index);
     }
 
     writer.flush()?;
     Ok(())
 }
 
-const LOAD_BATCH_LENGTH: usize = 32 * 1024;
+fn lock_group_indices(lock_hash: &[u8; 32]) -> Vec<usize> {
+    QueryIter::new(high_level::load_cell_lock_hash, Source::Input)
+        .enumerate()
+        .filter(|(_, hash)| hash == lock_hash)
+        .map(|(i, _)| i)
+        .collect()
+}
 
-struct InitialLoadData {
-    index: usize,
-    source: Source,
-    full_length: usize,
-    buffer: [u8; LOAD_BATCH_LENGTH],
+/// A writer wrapper keeping track of how many bytes have been written, so the
+/// boundaries of each preimage segment can be emitted via debug syscalls.
+/// Unlike ckb_std::debug!, the output here does not depend on debug assertions,
+/// enabling the trace feature is the only switch.
+#[cfg(feature = "trace")]
+struct TraceWriter<'a, W> {
+    inner: &'a mut W,
+    offset: usize,
+    segment_start: usize,
 }
 
-fn load_initial<F>(load_fn: F, index: usize, source: Source) -> Result<InitialLoadData, SysError>
-where
-    F: Fn(&mut [u8], usize, usize, Source) -> Result<usize, SysError>,
-{
-    let mut buffer = [0u8; LOAD_BATCH_LENGTH];
-    let full_length = match load_fn(&mut buffer, 0, index, source) {
-        Ok(actual_length) => actual_length,
-        Err(SysError::LengthNotEnough(actual_length)) => actual_length,
-        Err(e) => return Err(e),
-    };
-    Ok(InitialLoadData {
-        index,
-        source,
-        full_length,
-        buffer,
-    })
+#[cfg(feature = "trace")]
+impl<'a, W: io::Write> TraceWriter<'a, W> {
+    fn new(inner: &'a mut W) -> Self {
+        TraceWriter {
+            inner,
+            offset: 0,
+            segment_start: 0,
+        }
+    }
+
+    fn end_segment(&mut self, label: core::fmt::Arguments) {
+        let start = core::mem::replace(&mut self.segment_start, self.offset);
+        syscalls::debug(alloc::format!(
+            "{}{}: offset {}, length {}",
+            TRACE_SEGMENT_PREFIX,
+            label,
+            start,
+            self.offset - start
+        ));
+    }
 }
 
+#[cfg(feature = "trace")]
+impl<W: io::Write> io::Write for TraceWriter<'_, W> {
+    fn write(&mut self, data: &[u8]) -> Result<usize, io::Error> {
+        let written = self.inner.write(data)?;
+        self.offset += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.inner.flush()
+    }
+}
+
+type LoadFn = fn(&mut [u8], usize, usize, Source) -> Result<usize, SysError>;
+
+/// Streams the item denoted by +index+ and +source+ into +writer+, optionally
+/// prefixed by its length. The first load call doubles as the length probe:
+/// the syscall reports the full length while filling +buffer+ with the first
+/// chunk, which is hashed right away instead of being loaded a second time.
+///
+/// Returns false when +index+ is out of bound, meaning there is nothing to load.
 fn load_and_hash<W, F>(
-    initial: InitialLoadData,
     load_fn: F,
+    index: usize,
+    source: Source,
+    with_length: bool,
+    buffer: &mut [u8],
     writer: &mut W,
-) -> Result<(), CkbTxMessageAllError>
+) -> Result<bool, CkbTxMessageAllError>
 where
     W: io::Write,
     F: Fn(&mut [u8], usize, usize, Source) -> Result<usize, SysError>,
 {
-    let InitialLoadData {
-        full_length,
-        index,
-        source,
-        mut buffer,
-    } = initial;
-    let mut loaded = if full_length > LOAD_BATCH_LENGTH {
-        LOAD_BATCH_LENGTH
-    } else {
-        full_length
+    let full_length = match load_fn(buffer, 0, index, source) {
+        Ok(actual_length) => actual_length,
+        Err(SysError::LengthNotEnough(actual_length)) => actual_length,
+        Err(SysError::IndexOutOfBound) => return Ok(false),
+        Err(e) => return Err(e.into()),
     };
+    if with_length {
+        write_length(full_length, writer)?;
+    }
+
+    let mut loaded = core::cmp::min(full_length, buffer.len());
     writer.write_all(&buffer[0..loaded])?;
 
     while loaded < full_length {
-        match load_fn(&mut buffer, loaded, index, source) {
+        match load_fn(buffer, loaded, index, source) {
             Ok(current_loaded) => {
                 assert!(loaded.checked_add(current_loaded).expect("overflow") == full_length);
                 writer.write_all(&buffer[0..current_loaded])?;
                 loaded += current_loaded;
             }
             Err(SysError::LengthNotEnough(_)) => {
-                assert!(loaded.checked_add(LOAD_BATCH_LENGTH).expect("overflow") < full_length);
-                writer.write_all(&buffer)?;
-                loaded += LOAD_BATCH_LENGTH;
+                assert!(loaded.checked_add(buffer.len()).expect("overflow") < full_length);
+                writer.write_all(buffer)?;
+                loaded += buffer.len();
             }
             Err(e) => return Err(e.into()),
         }
     }
 
-    Ok(())
+    Ok(true)
 }
 
 #[inline]
@@ -0,0 +1,144 @@
+//! Shares one CKB_TX_MESSAGE_ALL implementation among scripts via spawn.
+//!
+//! The parent script calls [spawn_ckb_tx_message_all_oracle], which spawns the
+//! oracle script from a cell dep, and reads the 32-byte message back via a pipe.
+//! The oracle script merely calls [run_ckb_tx_message_all_oracle] in its entry.
+//! A spawned process shares the script group of its parent, so the oracle
+//! generates CKB_TX_MESSAGE_ALL for the parent's group unless a named lock
+//! script group is requested.
+use crate::{
+    ckb_tx_message_all_in_ckb_vm::{
+        generate_ckb_tx_message_all_for_group, CkbTxMessageAllError, ScriptGroup,
+        DEFAULT_LOAD_BUFFER_LENGTH,
+    },
+    hasher::{HashAlgorithm, Hasher},
+};
+use alloc::{ffi::CString, vec, vec::Vec};
+use ckb_std::{
+    ckb_types::core::ScriptHashType,
+    env,
+    error::SysError,
+    high_level::{self, decode_hex, encode_hex},
+    syscalls,
+};
+use core::ffi::CStr;
+
+#[derive(Debug)]
+pub enum OracleError {
+    Syscall(SysError),
+    InvalidArguments,
+    Generation(CkbTxMessageAllError),
+    ChildExit(i8),
+}
+
+impl From<SysError> for OracleError {
+    fn from(e: SysError) -> Self {
+        OracleError::Syscall(e)
+    }
+}
+
+impl From<CkbTxMessageAllError> for OracleError {
+    fn from(e: CkbTxMessageAllError) -> Self {
+        OracleError::Generation(e)
+    }
+}
+
+/// Spawns the oracle script located in cell deps via +code_hash+ and
+/// +hash_type+, returning CKB_TX_MESSAGE_ALL of +group+ hashed with +algorithm+.
+pub fn spawn_ckb_tx_message_all_oracle(
+    code_hash: &[u8],
+    hash_type: ScriptHashType,
+    algorithm: HashAlgorithm,
+    group: &ScriptGroup,
+) -> Result<[u8; 32], OracleError> {
+    let (read_fd, write_fd) = syscalls::pipe()?;
+
+    let argv = encode_oracle_argv(algorithm, group);
+    let argv: Vec<&CStr> = argv.iter().map(|arg| arg.as_c_str()).collect();
+    // Ownership of the write end is transferred to the oracle by spawn, there
+    // is no need(and no way) to close it in the parent.
+    let pid = high_level::spawn_cell(code_hash, hash_type, &argv, &[write_fd])?;
+
+    // Pipes in CKB-VM are not buffered, the oracle's write completes only
+    // when we read here, so the message must be read before waiting.
+    let mut message = [0u8; 32];
+    let read_result = read_exact(read_fd, &mut message);
+    let exit_code = syscalls::wait(pid)?;
+    if exit_code != 0 {
+        return Err(OracleError::ChildExit(exit_code));
+    }
+    read_result?;
+
+    Ok(message)
+}
+
+/// Entry of the oracle script: it decodes arguments passed by the parent,
+/// generates CKB_TX_MESSAGE_ALL, then writes the 32-byte message to the
+/// first inherited file descriptor.
+pub fn run_ckb_tx_message_all_oracle() -> Result<(), OracleError> {
+    let argv: Vec<&CStr> = env::argv().iter().map(|arg| &**arg).collect();
+    let (algorithm, group) = decode_oracle_argv(&argv)?;
+    let fd = *high_level::inherited_fds()
+        .first()
+        .ok_or(OracleError::InvalidArguments)?;
+
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = [0u8; DEFAULT_LOAD_BUFFER_LENGTH];
+    generate_ckb_tx_message_all_for_group(&mut hasher, &mut buffer, &group)?;
+
+    write_all(fd, &hasher.hash())?;
+    syscalls::close(fd)?;
+    Ok(())
+}
+
+/// Oracle arguments are the hex encoded algorithm id, optionally followed
+/// by the hex encoded lock script hash of a named group.
+pub fn encode_oracle_argv(algorithm: HashAlgorithm, group: &ScriptGroup) -> Vec<CString> {
+    let mut argv = vec![encode_hex(&[algorithm as u8])];
+    if let ScriptGroup::Lock(lock_hash) = group {
+        argv.push(encode_hex(lock_hash));
+    }
+    argv
+}
+
+pub fn decode_oracle_argv(argv: &[&CStr]) -> Result<(HashAlgorithm, ScriptGroup), OracleError> {
+    let algorithm = match argv.first().map(|arg| decode_hex(arg)) {
+        Some(Ok(id)) if id.len() == 1 => {
+            HashAlgorithm::try_from(id[0]).map_err(|_| OracleError::InvalidArguments)?
+        }
+        _ => return Err(OracleError::InvalidArguments),
+    };
+    let group = match argv.get(1).map(|arg| decode_hex(arg)) {
+        None => ScriptGroup::Current,
+        Some(Ok(lock_hash)) => ScriptGroup::Lock(
+            lock_hash
+                .try_into()
+                .map_err(|_| OracleError::InvalidArguments)?,
+        ),
+        Some(Err(_)) => return Err(OracleError::InvalidArguments),
+    };
+    if argv.len() > 2 {
+        return Err(OracleError::InvalidArguments);
+    }
+    Ok((algorithm, group))
+}
+
+fn read_exact(fd: u64, buffer: &mut [u8]) -> Result<(), SysError> {
+    let mut read = 0;
+    while read < buffer.len() {
+        let current = syscalls::read(fd, &mut buffer[read..])?;
+        if current == 0 {
+            return Err(SysError::OtherEndClosed);
+        }
+        read += current;
+    }
+    Ok(())
+}
+
+fn write_all(fd: u64, data: &[u8]) -> Result<(), SysError> {
+    let mut written = 0;
+    while written < data.len() {
+        written += syscalls::write(fd, &data[written..])?;
+    }
+    Ok(())
+}
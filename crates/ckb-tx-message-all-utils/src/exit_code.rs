@@ -0,0 +1,37 @@
+/// Exit codes shared by the CKB_TX_MESSAGE_ALL assert scripts, written in
+/// Rust & C alike, so a failure reports the same reason regardless of the
+/// implementation. exit_codes.h in c-assert-ckb-tx-message-all mirrors the
+/// values, which shall never change.
+///
+/// Codes 94 - 99 denote failures before a lock gets to verify anything, they
+/// are reserved for all locks built on ckb-tx-message-all-lock. Codes below
+/// 94 are lock specific, HashMismatch, WrongLockLength & InvalidArgs are
+/// only used by the assert scripts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i8)]
+pub enum ExitCode {
+    /// The lock field does not contain the CKB_TX_MESSAGE_ALL hash
+    HashMismatch = 1,
+    /// The lock field is not a 32-byte hash
+    WrongLockLength = 2,
+    /// Script args do not follow the layout expected by the script
+    InvalidArgs = 3,
+    /// Script args denote a sighash mode that is not supported
+    UnknownSighashMode = 94,
+    /// Script args denote a hash algorithm that is not supported
+    UnknownHashAlgorithm = 95,
+    /// A syscall fails, e.g., current script group has no witness
+    Syscall = 96,
+    /// The first witness of current script group is not a valid WitnessArgs
+    InvalidWitnessArgs = 97,
+    /// The first witness of current script group has no lock field
+    MissingLock = 98,
+    /// CKB_TX_MESSAGE_ALL cannot be generated for other reasons
+    Generation = 99,
+}
+
+impl From<ExitCode> for i8 {
+    fn from(code: ExitCode) -> i8 {
+        code as i8
+    }
+}
@@ -0,0 +1,257 @@
+//! A self-contained fixture format, so CKB_TX_MESSAGE_ALL implementations in
+//! any language can be tested without understanding `MockTransaction`. A
+//! fixture is a single JSON document, where all binary fields are in hex
+//! notation(no 0x prefix):
+//!
+//! ```json
+//! {
+//!   "message_version": 1,
+//!   "sighash_mode": 0,
+//!   "transaction": "<molecule serialized Transaction>",
+//!   "inputs": [{ "output": "<molecule serialized CellOutput>", "data": "<cell data>" }],
+//!   "group": [0, 2],
+//!   "preimage": "<full preimage>",
+//!   "hashes": { "blake2b": "...", "keccak256": "...", "sha256": "..." },
+//!   "failure": null
+//! }
+//! ```
+//!
+//! `inputs` are resolved input cells in the same order as inputs of the
+//! transaction. `group` contains indices of all input cells in current script
+//! group, i.e., those sharing the lock script of the first one. When no
+//! preimage can be generated, `preimage` is null, `hashes` is empty, and
+//! `failure` denotes the reason, see [CkbTxMessageAllError::reason].
+use crate::{
+    ckb_tx_message_all_from_mock_tx::{
+        generate_ckb_tx_message, generate_ckb_tx_message_all_with_version, locate_inputs,
+        CkbTxMessageAllError, ScriptOrIndex,
+    },
+    hasher::{HashAlgorithm, Hasher},
+    message_version::{MessageVersion, UnknownMessageVersion},
+    sighash_mode::{SighashMode, UnknownSighashMode},
+};
+use ckb_gen_types::{
+    bytes::Bytes,
+    packed::{CellOutput, Transaction},
+    prelude::*,
+};
+use ckb_mock_tx_types::MockTransaction;
+use molecule::error::VerificationError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Hash algorithms of a fixture's hashes, keyed by their names, which are
+/// the same as hash algorithm names used by the test vector generator
+pub const FIXTURE_HASH_ALGORITHMS: [(&str, HashAlgorithm); 3] = [
+    ("blake2b", HashAlgorithm::CkbBlake2b),
+    ("sha256", HashAlgorithm::Sha256),
+    ("keccak256", HashAlgorithm::Keccak256),
+];
+
+#[derive(Debug)]
+pub enum FixtureError {
+    Json(serde_json::Error),
+    Hex(hex::FromHexError),
+    Molecule(VerificationError),
+    MessageVersion(UnknownMessageVersion),
+    SighashMode(UnknownSighashMode),
+    /// Inputs do not match inputs of the transaction
+    InvalidMockTx,
+    /// Group, regenerated preimage, hashes or failure differ from the fixture
+    Mismatch(&'static str),
+}
+
+impl From<serde_json::Error> for FixtureError {
+    fn from(e: serde_json::Error) -> Self {
+        FixtureError::Json(e)
+    }
+}
+
+impl From<hex::FromHexError> for FixtureError {
+    fn from(e: hex::FromHexError) -> Self {
+        FixtureError::Hex(e)
+    }
+}
+
+impl From<VerificationError> for FixtureError {
+    fn from(e: VerificationError) -> Self {
+        FixtureError::Molecule(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub message_version: u8,
+    pub sighash_mode: u8,
+    pub transaction: String,
+    pub inputs: Vec<FixtureInput>,
+    pub group: Vec<usize>,
+    pub preimage: Option<String>,
+    pub hashes: BTreeMap<String, String>,
+    pub failure: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureInput {
+    pub output: String,
+    pub data: String,
+}
+
+impl Fixture {
+    /// Generates a fixture for the script group of input cells at +group+
+    pub fn generate(
+        tx: &Transaction,
+        inputs: &[(CellOutput, Bytes)],
+        group: &[usize],
+        version: MessageVersion,
+        mode: SighashMode,
+    ) -> Self {
+        let (preimage, hashes, failure) = match generate_preimage(tx, inputs, group, version, mode)
+        {
+            Ok(preimage) => (Some(hex::encode(&preimage)), hash_preimage(&preimage), None),
+            Err(e) => (None, BTreeMap::new(), Some(e.reason().to_string())),
+        };
+
+        Fixture {
+            message_version: version.into(),
+            sighash_mode: mode.into(),
+            transaction: hex::encode(tx.as_slice()),
+            inputs: inputs
+                .iter()
+                .map(|(output, data)| FixtureInput {
+                    output: hex::encode(output.as_slice()),
+                    data: hex::encode(data),
+                })
+                .collect(),
+            group: group.to_vec(),
+            preimage,
+            hashes,
+            failure,
+        }
+    }
+
+    /// Same as [Fixture::generate], resolving input cells from +mock_tx+
+    pub fn generate_from_mock_tx(
+        mock_tx: &MockTransaction,
+        group: &[usize],
+        version: MessageVersion,
+        mode: SighashMode,
+    ) -> Result<Self, FixtureError> {
+        let inputs = locate_inputs(mock_tx).map_err(|_| FixtureError::InvalidMockTx)?;
+        Ok(Self::generate(&mock_tx.tx, &inputs, group, version, mode))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, FixtureError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("to json")
+    }
+
+    pub fn message_version(&self) -> Result<MessageVersion, FixtureError> {
+        MessageVersion::try_from(self.message_version).map_err(FixtureError::MessageVersion)
+    }
+
+    pub fn sighash_mode(&self) -> Result<SighashMode, FixtureError> {
+        SighashMode::try_from(self.sighash_mode).map_err(FixtureError::SighashMode)
+    }
+
+    pub fn transaction(&self) -> Result<Transaction, FixtureError> {
+        Ok(Transaction::from_slice(&hex::decode(&self.transaction)?)?)
+    }
+
+    /// Resolved input cells, in the same order as inputs of the transaction
+    pub fn inputs(&self) -> Result<Vec<(CellOutput, Bytes)>, FixtureError> {
+        self.inputs
+            .iter()
+            .map(|input| {
+                let output = CellOutput::from_slice(&hex::decode(&input.output)?)?;
+                let data = hex::decode(&input.data)?.into();
+                Ok((output, data))
+            })
+            .collect()
+    }
+
+    pub fn preimage(&self) -> Result<Option<Vec<u8>>, FixtureError> {
+        Ok(self.preimage.as_ref().map(hex::decode).transpose()?)
+    }
+
+    /// Regenerates the preimage from the transaction & inputs, ensuring it,
+    /// the hashes and the failure reason all match the fixture. The group
+    /// shall contain every input cell sharing the lock of its first one.
+    pub fn check(&self) -> Result<(), FixtureError> {
+        let tx = self.transaction()?;
+        let inputs = self.inputs()?;
+        if tx.raw().inputs().len() != inputs.len() {
+            return Err(FixtureError::InvalidMockTx);
+        }
+        if let Some(first) = self.group.first() {
+            let lock = inputs
+                .get(*first)
+                .map(|(output, _)| output.lock())
+                .ok_or(FixtureError::Mismatch("group"))?;
+            let group: Vec<usize> = (0..inputs.len())
+                .filter(|i| inputs[*i].0.lock() == lock)
+                .collect();
+            if group != self.group {
+                return Err(FixtureError::Mismatch("group"));
+            }
+        }
+        let regenerated = Self::generate(
+            &tx,
+            &inputs,
+            &self.group,
+            self.message_version()?,
+            self.sighash_mode()?,
+        );
+
+        if regenerated.preimage != self.preimage {
+            return Err(FixtureError::Mismatch("preimage"));
+        }
+        if regenerated.hashes != self.hashes {
+            return Err(FixtureError::Mismatch("hashes"));
+        }
+        if regenerated.failure != self.failure {
+            return Err(FixtureError::Mismatch("failure"));
+        }
+        Ok(())
+    }
+}
+
+fn generate_preimage(
+    tx: &Transaction,
+    inputs: &[(CellOutput, Bytes)],
+    group: &[usize],
+    version: MessageVersion,
+    mode: SighashMode,
+) -> Result<Vec<u8>, CkbTxMessageAllError> {
+    let index = *group
+        .first()
+        .ok_or(CkbTxMessageAllError::UnknownScriptGroup)?;
+    let mut preimage = vec![];
+    match mode {
+        SighashMode::All => generate_ckb_tx_message_all_with_version(
+            tx,
+            inputs,
+            ScriptOrIndex::Index(index),
+            version,
+            &mut preimage,
+        )?,
+        mode => {
+            generate_ckb_tx_message(tx, inputs, ScriptOrIndex::Index(index), mode, &mut preimage)?
+        }
+    }
+    Ok(preimage)
+}
+
+fn hash_preimage(preimage: &[u8]) -> BTreeMap<String, String> {
+    FIXTURE_HASH_ALGORITHMS
+        .iter()
+        .map(|(name, algorithm)| {
+            let mut hasher = Hasher::new(*algorithm);
+            hasher.update(preimage);
+            (name.to_string(), hex::encode(hasher.hash()))
+        })
+        .collect()
+}
@@ -0,0 +1,96 @@
+use ckb_hash::{new_blake2b, Blake2b};
+use ckb_rust_std::io;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+/// Hash algorithms that can be used to digest CKB_TX_MESSAGE_ALL preimage.
+/// The numeric values are stable identifiers, which are used in script args
+/// and spawn arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HashAlgorithm {
+    /// Blake2b with 32-byte output and `ckb-default-hash` as personalization
+    CkbBlake2b = 0,
+    Sha256 = 1,
+    Keccak256 = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownHashAlgorithm(pub u8);
+
+impl TryFrom<u8> for HashAlgorithm {
+    type Error = UnknownHashAlgorithm;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(HashAlgorithm::CkbBlake2b),
+            1 => Ok(HashAlgorithm::Sha256),
+            2 => Ok(HashAlgorithm::Keccak256),
+            _ => Err(UnknownHashAlgorithm(value)),
+        }
+    }
+}
+
+/// A hasher producing 32-byte hashes using one of the supported algorithms.
+pub enum Hasher {
+    CkbBlake2b(Blake2b),
+    Sha256(Sha256),
+    Keccak256(Keccak256),
+}
+
+impl Hasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::CkbBlake2b => Hasher::CkbBlake2b(new_blake2b()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Keccak256 => Hasher::Keccak256(Keccak256::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::CkbBlake2b(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Keccak256(h) => h.update(data),
+        }
+    }
+
+    pub fn hash(self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        match self {
+            Hasher::CkbBlake2b(h) => h.finalize(&mut result),
+            Hasher::Sha256(h) => result.copy_from_slice(&h.finalize()),
+            Hasher::Keccak256(h) => result.copy_from_slice(&h.finalize()),
+        }
+        result
+    }
+}
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Hasher::new(HashAlgorithm::CkbBlake2b)
+    }
+}
+
+impl io::Write for Hasher {
+    fn write(&mut self, data: &[u8]) -> Result<usize, io::Error> {
+        self.update(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for Hasher {
+    fn write(&mut self, data: &[u8]) -> Result<usize, std::io::Error> {
+        self.update(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
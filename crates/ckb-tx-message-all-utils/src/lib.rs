@@ -1,8 +1,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-#[cfg(not(feature = "std"))]
 extern crate alloc;
 
 #[cfg(feature = "std")]
 pub mod ckb_tx_message_all_from_mock_tx;
 pub mod ckb_tx_message_all_in_ckb_vm;
+pub mod ckb_tx_message_all_oracle;
+pub mod exit_code;
+#[cfg(feature = "std")]
+pub mod fixture;
+pub mod hasher;
+pub mod message_version;
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1_blake160;
+pub mod sighash_mode;
+pub mod verifier;
@@ -0,0 +1,42 @@
+/// Revisions of the CKB_TX_MESSAGE_ALL specification. The numeric values are
+/// stable identifiers, which are kept in test vector metadata.
+///
+/// A new revision(e.g., one that also commits contents of cell deps) is added
+/// as a new variant. The generators match on the version at each point where
+/// revisions differ, so adding a variant fails to compile until every
+/// generator handles it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MessageVersion {
+    /// The original specification: tx hash, contents of all input cells,
+    /// input_type & output_type of the first witness of current script group,
+    /// the remaining witnesses of the group, then witnesses of no matching
+    /// input cells
+    #[default]
+    V1 = 1,
+}
+
+impl MessageVersion {
+    /// The latest revision of the specification
+    pub const LATEST: MessageVersion = MessageVersion::V1;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownMessageVersion(pub u8);
+
+impl TryFrom<u8> for MessageVersion {
+    type Error = UnknownMessageVersion;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(MessageVersion::V1),
+            _ => Err(UnknownMessageVersion(value)),
+        }
+    }
+}
+
+impl From<MessageVersion> for u8 {
+    fn from(version: MessageVersion) -> u8 {
+        version as u8
+    }
+}
@@ -0,0 +1,68 @@
+//! secp256k1 recoverable signatures on CKB_TX_MESSAGE_ALL hash, where a
+//! public key is identified by its blake160 hash: the first 20 bytes of the
+//! ckb flavored blake2b hash of the compressed public key. It is the scheme
+//! of the default secp256k1 lock on CKB.
+//!
+//! The exit codes below are shared by scripts verifying such signatures in
+//! place and via delegation(see [crate::verifier]), so the same failure is
+//! reported with the same code either way.
+use ckb_hash::blake2b_256;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+pub const PUBKEY_HASH_LENGTH: usize = 20;
+/// 64 bytes of r & s, followed by 1 byte of recovery id
+pub const SIGNATURE_LENGTH: usize = 65;
+
+/// Arguments(e.g., script args) are of the wrong length
+pub const ERROR_INVALID_ARGUMENTS: i8 = 1;
+/// See [Secp256k1Error::InvalidSignature]
+pub const ERROR_INVALID_SIGNATURE: i8 = 3;
+/// See [Secp256k1Error::PubkeyHashMismatch]
+pub const ERROR_PUBKEY_HASH_MISMATCH: i8 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Secp256k1Error {
+    /// The signature is malformed, or no public key can be recovered from it
+    InvalidSignature,
+    /// The recovered public key does not match the public key hash
+    PubkeyHashMismatch,
+}
+
+impl Secp256k1Error {
+    pub fn exit_code(&self) -> i8 {
+        match self {
+            Secp256k1Error::InvalidSignature => ERROR_INVALID_SIGNATURE,
+            Secp256k1Error::PubkeyHashMismatch => ERROR_PUBKEY_HASH_MISMATCH,
+        }
+    }
+}
+
+/// Recovers the blake160 hash of the public key which signed +message+
+pub fn recover_blake160(
+    message: &[u8; 32],
+    signature: &[u8; SIGNATURE_LENGTH],
+) -> Result<[u8; PUBKEY_HASH_LENGTH], Secp256k1Error> {
+    let recovery_id =
+        RecoveryId::from_byte(signature[64]).ok_or(Secp256k1Error::InvalidSignature)?;
+    let signature =
+        Signature::from_slice(&signature[0..64]).map_err(|_| Secp256k1Error::InvalidSignature)?;
+
+    let pubkey = VerifyingKey::recover_from_prehash(message, &signature, recovery_id)
+        .map_err(|_| Secp256k1Error::InvalidSignature)?;
+    let hash = blake2b_256(pubkey.to_encoded_point(true).as_bytes());
+    let mut pubkey_hash = [0u8; PUBKEY_HASH_LENGTH];
+    pubkey_hash.copy_from_slice(&hash[0..PUBKEY_HASH_LENGTH]);
+    Ok(pubkey_hash)
+}
+
+/// Ensures +message+ is signed by the public key of +pubkey_hash+
+pub fn verify_blake160(
+    message: &[u8; 32],
+    signature: &[u8; SIGNATURE_LENGTH],
+    pubkey_hash: &[u8],
+) -> Result<(), Secp256k1Error> {
+    if recover_blake160(message, signature)? != pubkey_hash {
+        return Err(Secp256k1Error::PubkeyHashMismatch);
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use alloc::vec::Vec;
+
+/// Experimental variants of CKB_TX_MESSAGE_ALL, committing to part of the
+/// transaction only, so open transactions can be evaluated on the same
+/// tooling. The numeric values are stable identifiers, which are used in
+/// script args.
+///
+/// [SighashMode::All] is CKB_TX_MESSAGE_ALL itself. Other modes are only
+/// defined for lock script groups, their preimage consists of:
+///
+/// * The mode id(1 byte)
+/// * The number of input cells in the group(u32 in little endian), followed
+///   by each of them in order: `CellInput`, `CellOutput`, then the length of
+///   cell data(u32 in little endian) & cell data
+/// * The number of committed output cells(u32 in little endian), followed
+///   by each of them in order: `CellOutput`, then the length of cell data
+///   (u32 in little endian) & cell data
+/// * The first witness of the group, same as CKB_TX_MESSAGE_ALL: the length
+///   of `input_type` & `input_type`, then the length of `output_type` &
+///   `output_type`
+/// * The remaining witnesses of the group, each prefixed by its length
+///
+/// Other input cells, cell deps, header deps & witnesses of no matching
+/// input cells are not committed, so others are free to add their own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SighashMode {
+    /// CKB_TX_MESSAGE_ALL, committing to the whole transaction
+    #[default]
+    All = 0,
+    /// Commits to input cells of the group, and output cells sharing indices
+    /// with them. An input cell with no output cell of the same index
+    /// commits no output cell.
+    SingleGroupOutputs = 1,
+    /// Commits to input cells of the group, and all output cells
+    AnyoneCanPay = 2,
+}
+
+impl SighashMode {
+    /// Indices of output cells committed by the mode, given indices of input
+    /// cells in the group and the number of output cells. All output cells
+    /// are committed by [SighashMode::All], via the tx hash.
+    pub fn committed_outputs(&self, group_indices: &[usize], output_count: usize) -> Vec<usize> {
+        match self {
+            SighashMode::All | SighashMode::AnyoneCanPay => (0..output_count).collect(),
+            SighashMode::SingleGroupOutputs => group_indices
+                .iter()
+                .copied()
+                .filter(|i| *i < output_count)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownSighashMode(pub u8);
+
+impl TryFrom<u8> for SighashMode {
+    type Error = UnknownSighashMode;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SighashMode::All),
+            1 => Ok(SighashMode::SingleGroupOutputs),
+            2 => Ok(SighashMode::AnyoneCanPay),
+            _ => Err(UnknownSighashMode(value)),
+        }
+    }
+}
+
+impl From<SighashMode> for u8 {
+    fn from(mode: SighashMode) -> u8 {
+        mode as u8
+    }
+}
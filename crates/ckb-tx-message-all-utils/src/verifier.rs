@@ -0,0 +1,101 @@
+//! Delegates signature verification to a separate script via spawn.
+//!
+//! A lock script calculates CKB_TX_MESSAGE_ALL by itself, then calls
+//! [spawn_verifier], passing the message together with the signature and
+//! the public key hash to a verifier script from cell deps. The verifier
+//! decodes them via [decode_verifier_argv], and signals the result via its
+//! exit code: 0 means the signature is valid. This way new signature
+//! algorithms can be plugged in by deploying a new verifier, while the lock
+//! script stays the same.
+use alloc::{ffi::CString, vec::Vec};
+use ckb_std::{
+    ckb_types::core::ScriptHashType,
+    error::SysError,
+    high_level::{self, decode_hex, encode_hex},
+    syscalls,
+};
+use core::ffi::CStr;
+
+#[derive(Debug)]
+pub enum VerifierError {
+    Syscall(SysError),
+    InvalidArguments,
+    ChildExit(i8),
+}
+
+impl From<SysError> for VerifierError {
+    fn from(e: SysError) -> Self {
+        VerifierError::Syscall(e)
+    }
+}
+
+/// Arguments passed from the lock script to the verifier script
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifierArgs {
+    /// Signature algorithm id, interpreted by the verifier
+    pub algorithm_id: u8,
+    pub pubkey_hash: Vec<u8>,
+    /// CKB_TX_MESSAGE_ALL hash
+    pub message: [u8; 32],
+    pub signature: Vec<u8>,
+}
+
+/// Spawns the verifier script located in cell deps via +code_hash+ and
+/// +hash_type+, succeeding only when the verifier exits with 0.
+pub fn spawn_verifier(
+    code_hash: &[u8],
+    hash_type: ScriptHashType,
+    args: &VerifierArgs,
+) -> Result<(), VerifierError> {
+    let argv = encode_verifier_argv(args);
+    let argv: Vec<&CStr> = argv.iter().map(|arg| arg.as_c_str()).collect();
+    let pid = high_level::spawn_cell(code_hash, hash_type, &argv, &[])?;
+
+    match syscalls::wait(pid)? {
+        0 => Ok(()),
+        exit_code => Err(VerifierError::ChildExit(exit_code)),
+    }
+}
+
+/// Verifier arguments are the algorithm id, public key hash, message and
+/// signature, each hex encoded in a separate argument.
+pub fn encode_verifier_argv(args: &VerifierArgs) -> Vec<CString> {
+    [
+        &[args.algorithm_id][..],
+        &args.pubkey_hash,
+        &args.message,
+        &args.signature,
+    ]
+    .iter()
+    .map(|arg| encode_hex(arg))
+    .collect()
+}
+
+pub fn decode_verifier_argv(argv: &[&CStr]) -> Result<VerifierArgs, VerifierError> {
+    if argv.len() != 4 {
+        return Err(VerifierError::InvalidArguments);
+    }
+    let mut decoded = argv
+        .iter()
+        .map(|arg| decode_hex(arg).map_err(|_| VerifierError::InvalidArguments))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+    let mut next = || decoded.next().expect("4 arguments");
+
+    let algorithm_id = match next()[..] {
+        [id] => id,
+        _ => return Err(VerifierError::InvalidArguments),
+    };
+    let pubkey_hash = next();
+    let message = next()
+        .try_into()
+        .map_err(|_| VerifierError::InvalidArguments)?;
+    let signature = next();
+
+    Ok(VerifierArgs {
+        algorithm_id,
+        pubkey_hash,
+        message,
+        signature,
+    })
+}
@@ -0,0 +1,40 @@
+use std::env;
+use std::path::PathBuf;
+
+// Headers under test live with the C assert script
+const HEADER_DIR: &str = "../../contracts/c-assert-ckb-tx-message-all";
+
+fn main() {
+    println!("cargo:rerun-if-changed=c");
+    println!("cargo:rerun-if-changed={}", HEADER_DIR);
+    println!("cargo:rerun-if-env-changed=CKB_C_STDLIB");
+    println!("cargo:rustc-check-cfg=cfg(c_header_harness)");
+
+    // ckb-c-stdlib is only needed for molecule headers & constants, syscalls
+    // are replaced by c/ckb_syscalls.h. It defaults to the git submodule.
+    let ckb_c_stdlib = env::var("CKB_C_STDLIB")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("../../deps/ckb-c-stdlib"));
+    if !ckb_c_stdlib.join("molecule/blockchain-api2.h").exists() {
+        if env::var_os("CARGO_FEATURE_SKIP_C_HEADER_HARNESS").is_some() {
+            return;
+        }
+        panic!(
+            "ckb-c-stdlib is not found at {}. Run `git submodule update --init`, \
+             set CKB_C_STDLIB, or enable the skip-c-header-harness feature to \
+             leave the C header harness out.",
+            ckb_c_stdlib.display()
+        );
+    }
+
+    cc::Build::new()
+        .file("c/harness.c")
+        // c/ckb_syscalls.h must take precedence over the one in ckb-c-stdlib
+        .include("c")
+        .include(HEADER_DIR)
+        .include(&ckb_c_stdlib)
+        .flag_if_supported("-Wno-unused-function")
+        .flag_if_supported("-Wno-unused-parameter")
+        .compile("ckb-tx-message-all-c-header");
+    println!("cargo:rustc-cfg=c_header_harness");
+}
@@ -0,0 +1,33 @@
+#ifndef CKB_TX_MESSAGE_ALL_HARNESS_SYSCALLS_H_
+#define CKB_TX_MESSAGE_ALL_HARNESS_SYSCALLS_H_
+
+/*
+ * Stands in for ckb_syscalls.h from ckb-c-stdlib, so the C header can be
+ * built natively. Syscalls declared here are implemented in Rust, backed by a
+ * MockTransaction, following the semantics of CKB-VM syscalls.
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+#include "ckb_consts.h"
+
+/*
+ * ckb-x64-simulator exports syscalls under the same names, rename them so the
+ * harness can be linked alongside it.
+ */
+#define ckb_load_tx_hash harness_load_tx_hash
+#define ckb_load_cell harness_load_cell
+#define ckb_load_cell_data harness_load_cell_data
+#define ckb_load_witness harness_load_witness
+
+int ckb_load_tx_hash(void* addr, uint64_t* len, size_t offset);
+int ckb_load_cell(void* addr, uint64_t* len, size_t offset, size_t index,
+                  size_t source);
+int ckb_load_cell_data(void* addr, uint64_t* len, size_t offset, size_t index,
+                       size_t source);
+int ckb_load_witness(void* addr, uint64_t* len, size_t offset, size_t index,
+                     size_t source);
+
+#endif /* CKB_TX_MESSAGE_ALL_HARNESS_SYSCALLS_H_ */
@@ -0,0 +1,31 @@
+#include <setjmp.h>
+
+/*
+ * Molecule readers terminate the script via MOL2_EXIT on malformed data, in
+ * the harness we jump back to the caller instead. The exit point is thread
+ * local like the mocked syscalls, since tests run in parallel.
+ */
+static _Thread_local jmp_buf harness_exit_point;
+
+static void harness_exit(int code) { longjmp(harness_exit_point, code); }
+
+#define MOL2_EXIT harness_exit
+/* Failures are reported via return values, keep test output clean */
+#define mol2_printf(...) ((void)0)
+
+#include "ckb_tx_message_all.h"
+
+/*
+ * Runs ckb_tx_message_all_generate, exited is set to 1 when the C header
+ * terminates the script instead of returning an error code.
+ */
+int ckb_tx_message_all_harness_generate(ckb_tx_message_write_func_t writer,
+                                        void* context, int* exited) {
+  *exited = 0;
+  int code = setjmp(harness_exit_point);
+  if (code != 0) {
+    *exited = 1;
+    return code;
+  }
+  return ckb_tx_message_all_generate(writer, context);
+}
@@ -0,0 +1,261 @@
+use ckb_mock_tx_types::MockTransaction;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    packed::{CellOutput, Script},
+    prelude::*,
+};
+use ckb_tx_message_all_utils::ckb_tx_message_all_from_mock_tx::ScriptOrIndex;
+use std::cell::RefCell;
+use std::os::raw::{c_int, c_void};
+
+// Values from ckb_consts.h
+const CKB_SUCCESS: c_int = 0;
+const CKB_INDEX_OUT_OF_BOUND: c_int = 1;
+const CKB_ITEM_MISSING: c_int = 2;
+
+const CKB_SOURCE_INPUT: usize = 1;
+const CKB_SOURCE_OUTPUT: usize = 2;
+const CKB_SOURCE_GROUP_INPUT: usize = 0x0100000000000001;
+const CKB_SOURCE_GROUP_OUTPUT: usize = 0x0100000000000002;
+
+type WriteFunc =
+    unsafe extern "C" fn(data: *const u8, length: usize, context: *mut c_void) -> c_int;
+
+extern "C" {
+    // Defined in c/harness.c
+    fn ckb_tx_message_all_harness_generate(
+        writer: WriteFunc,
+        context: *mut c_void,
+        exited: *mut c_int,
+    ) -> c_int;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CHeaderError {
+    /// Input cells cannot be resolved, or the script group does not exist
+    InvalidMockTx,
+    /// ckb_tx_message_all_generate returns a non-zero error code
+    Returned(i32),
+    /// The C header terminates the script via MOL2_EXIT with the code
+    Exited(i32),
+}
+
+/// Generates CKB_TX_MESSAGE_ALL preimage via the C header, using the same
+/// arguments as generate_ckb_tx_message_all_from_mock_tx.
+pub fn generate_ckb_tx_message_all_from_c_header(
+    mock_tx: &MockTransaction,
+    script_or_index: ScriptOrIndex,
+) -> Result<Vec<u8>, CHeaderError> {
+    let syscalls = MockSyscalls::new(mock_tx, script_or_index)?;
+    SYSCALLS.with(|s| *s.borrow_mut() = Some(syscalls));
+
+    let mut preimage: Vec<u8> = vec![];
+    let mut exited = 0;
+    let code = unsafe {
+        ckb_tx_message_all_harness_generate(
+            write_to_vec,
+            &mut preimage as *mut Vec<u8> as *mut c_void,
+            &mut exited,
+        )
+    };
+
+    SYSCALLS.with(|s| *s.borrow_mut() = None);
+    match (code, exited) {
+        (0, _) => Ok(preimage),
+        (code, 0) => Err(CHeaderError::Returned(code)),
+        (code, _) => Err(CHeaderError::Exited(code)),
+    }
+}
+
+unsafe extern "C" fn write_to_vec(data: *const u8, length: usize, context: *mut c_void) -> c_int {
+    let preimage = &mut *(context as *mut Vec<u8>);
+    if length > 0 {
+        preimage.extend_from_slice(std::slice::from_raw_parts(data, length));
+    }
+    0
+}
+
+/// Data served by mocked syscalls for the running C header
+struct MockSyscalls {
+    tx_hash: [u8; 32],
+    inputs: Vec<(CellOutput, Bytes)>,
+    outputs: Vec<(CellOutput, Bytes)>,
+    witnesses: Vec<Bytes>,
+    group_inputs: Vec<usize>,
+    group_outputs: Vec<usize>,
+}
+
+thread_local! {
+    static SYSCALLS: RefCell<Option<MockSyscalls>> = const { RefCell::new(None) };
+}
+
+impl MockSyscalls {
+    fn new(
+        mock_tx: &MockTransaction,
+        script_or_index: ScriptOrIndex,
+    ) -> Result<Self, CHeaderError> {
+        let tx = &mock_tx.tx;
+        let mut inputs = vec![];
+        for input in tx.raw().inputs() {
+            let mock_input = mock_tx
+                .mock_info
+                .inputs
+                .iter()
+                .find(|mock_input| mock_input.input == input)
+                .ok_or(CHeaderError::InvalidMockTx)?;
+            inputs.push((mock_input.output.clone(), mock_input.data.clone()));
+        }
+        let outputs: Vec<_> = tx
+            .raw()
+            .outputs()
+            .into_iter()
+            .zip(tx.raw().outputs_data())
+            .map(|(output, data)| (output, data.raw_data()))
+            .collect();
+
+        let (lock, type_script): (Option<Script>, Option<Script>) = match script_or_index {
+            ScriptOrIndex::Script(script) => (Some(script), None),
+            ScriptOrIndex::Index(i) => (
+                Some(inputs.get(i).ok_or(CHeaderError::InvalidMockTx)?.0.lock()),
+                None,
+            ),
+            ScriptOrIndex::TypeScript(script) => (None, Some(script)),
+        };
+        let in_group = |cell: &CellOutput| match &lock {
+            Some(lock) => &cell.lock() == lock,
+            None => cell.type_().to_opt() == type_script,
+        };
+        let group_inputs = group_indices(&inputs, in_group);
+        // Lock script groups only contain input cells
+        let group_outputs = if lock.is_some() {
+            vec![]
+        } else {
+            group_indices(&outputs, in_group)
+        };
+        if group_inputs.is_empty() && group_outputs.is_empty() {
+            return Err(CHeaderError::InvalidMockTx);
+        }
+
+        Ok(MockSyscalls {
+            tx_hash: tx.calc_tx_hash().unpack(),
+            inputs,
+            outputs,
+            witnesses: tx.witnesses().into_iter().map(|w| w.raw_data()).collect(),
+            group_inputs,
+            group_outputs,
+        })
+    }
+
+    fn cell(&self, index: usize, source: usize) -> Result<&(CellOutput, Bytes), c_int> {
+        let (cells, index) = match source {
+            CKB_SOURCE_INPUT => (&self.inputs, Some(index)),
+            CKB_SOURCE_OUTPUT => (&self.outputs, Some(index)),
+            CKB_SOURCE_GROUP_INPUT => (&self.inputs, self.group_inputs.get(index).copied()),
+            CKB_SOURCE_GROUP_OUTPUT => (&self.outputs, self.group_outputs.get(index).copied()),
+            // Cell deps are not mocked
+            _ => return Err(CKB_ITEM_MISSING),
+        };
+        index
+            .and_then(|i| cells.get(i))
+            .ok_or(CKB_INDEX_OUT_OF_BOUND)
+    }
+
+    fn witness(&self, index: usize, source: usize) -> Result<&Bytes, c_int> {
+        let index = match source {
+            CKB_SOURCE_INPUT | CKB_SOURCE_OUTPUT => Some(index),
+            CKB_SOURCE_GROUP_INPUT => self.group_inputs.get(index).copied(),
+            CKB_SOURCE_GROUP_OUTPUT => self.group_outputs.get(index).copied(),
+            _ => return Err(CKB_ITEM_MISSING),
+        };
+        index
+            .and_then(|i| self.witnesses.get(i))
+            .ok_or(CKB_INDEX_OUT_OF_BOUND)
+    }
+}
+
+fn group_indices<F>(cells: &[(CellOutput, Bytes)], in_group: F) -> Vec<usize>
+where
+    F: Fn(&CellOutput) -> bool,
+{
+    cells
+        .iter()
+        .enumerate()
+        .filter(|(_, (cell, _))| in_group(cell))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Runs a mocked syscall, +f+ locates the data to load
+fn load<F>(addr: *mut c_void, len: *mut u64, offset: usize, f: F) -> c_int
+where
+    F: FnOnce(&MockSyscalls) -> Result<Bytes, c_int>,
+{
+    SYSCALLS.with(|s| {
+        let s = s.borrow();
+        let s = s
+            .as_ref()
+            .expect("syscalls are only available in the harness");
+        match f(s) {
+            Ok(data) => unsafe { store(addr, len, offset, &data) },
+            Err(e) => e,
+        }
+    })
+}
+
+// Follows store_data in CKB: the full length starting from offset is always
+// written back, while only as much data as the buffer can hold is copied.
+unsafe fn store(addr: *mut c_void, len: *mut u64, offset: usize, data: &[u8]) -> c_int {
+    let offset = offset.min(data.len());
+    let full_size = data.len() - offset;
+    let real_size = (*len as usize).min(full_size);
+    if real_size > 0 {
+        std::ptr::copy_nonoverlapping(data[offset..].as_ptr(), addr as *mut u8, real_size);
+    }
+    *len = full_size as u64;
+    CKB_SUCCESS
+}
+
+// Syscalls are renamed with a harness_ prefix in c/ckb_syscalls.h
+#[no_mangle]
+extern "C" fn harness_load_tx_hash(addr: *mut c_void, len: *mut u64, offset: usize) -> c_int {
+    load(addr, len, offset, |s| {
+        Ok(Bytes::copy_from_slice(&s.tx_hash))
+    })
+}
+
+#[no_mangle]
+extern "C" fn harness_load_cell(
+    addr: *mut c_void,
+    len: *mut u64,
+    offset: usize,
+    index: usize,
+    source: usize,
+) -> c_int {
+    load(addr, len, offset, |s| {
+        s.cell(index, source).map(|(cell, _)| cell.as_bytes())
+    })
+}
+
+#[no_mangle]
+extern "C" fn harness_load_cell_data(
+    addr: *mut c_void,
+    len: *mut u64,
+    offset: usize,
+    index: usize,
+    source: usize,
+) -> c_int {
+    load(addr, len, offset, |s| {
+        s.cell(index, source).map(|(_, data)| data.clone())
+    })
+}
+
+#[no_mangle]
+extern "C" fn harness_load_witness(
+    addr: *mut c_void,
+    len: *mut u64,
+    offset: usize,
+    index: usize,
+    source: usize,
+) -> c_int {
+    load(addr, len, offset, |s| s.witness(index, source).cloned())
+}
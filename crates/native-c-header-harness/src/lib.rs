@@ -0,0 +1,17 @@
+//! A native harness for ckb_tx_message_all.h & witness_args_lazy_utils.h.
+//! The C header is built natively with mocked syscalls backed by a
+//! MockTransaction, so its output can be compared byte-for-byte with
+//! generate_ckb_tx_message_all_from_mock_tx, without building a RISC-V
+//! contract first.
+//!
+//! ckb-c-stdlib is required for molecule headers, when it cannot be found the
+//! build fails unless the skip-c-header-harness feature is enabled, see
+//! build.rs.
+
+#[cfg(c_header_harness)]
+mod harness;
+#[cfg(c_header_harness)]
+pub use harness::*;
+
+#[cfg(all(test, c_header_harness))]
+mod tests;
@@ -0,0 +1,218 @@
+use super::*;
+use ckb_mock_tx_types::MockTransaction;
+use ckb_testtool::{
+    ckb_types::{bytes::Bytes, core::TransactionView, packed::WitnessArgsReader, prelude::*},
+    context::Context,
+};
+use ckb_tx_message_all_utils::ckb_tx_message_all_from_mock_tx::{
+    generate_ckb_tx_message_all_from_mock_tx, ScriptOrIndex,
+};
+use proptest::prelude::*;
+use test_utils::*;
+
+// Scripts are never executed, they only need distinct data hashes
+fn _bins() -> (Bytes, Bytes) {
+    (
+        Bytes::from_static(b"ckb-tx-message-all-lock"),
+        Bytes::from_static(b"always-success"),
+    )
+}
+
+// Error codes of molecule2_reader.h, returned by the C header on an invalid
+// WitnessArgs
+const MOL2_ERR_TOTAL_SIZE: i32 = 0x01;
+const MOL2_ERR_HEADER: i32 = 0x02;
+const MOL2_ERR_OFFSET: i32 = 0x03;
+const MOL2_ERR_FIELD_COUNT: i32 = 0x06;
+const MOL2_ERR_DATA: i32 = 0x07;
+const MOL2_ERR_OVERFLOW: i32 = 0x08;
+
+fn _c_header_preimage(
+    context: &Context,
+    tx: &TransactionView,
+    index: usize,
+) -> Result<Vec<u8>, CHeaderError> {
+    let mock_tx: MockTransaction = context.dump_tx(tx).expect("dump tx").into();
+    generate_ckb_tx_message_all_from_c_header(&mock_tx, ScriptOrIndex::Index(index))
+}
+
+/// Asserts that the C header agrees with the Rust generator: either both
+/// produce the same preimage, or both fail. Returns whether they succeed.
+fn _compare(context: &Context, tx: &TransactionView, index: usize) -> bool {
+    let mock_tx: MockTransaction = context.dump_tx(tx).expect("dump tx").into();
+
+    let mut expected = vec![];
+    let rust_result = generate_ckb_tx_message_all_from_mock_tx(
+        &mock_tx,
+        ScriptOrIndex::Index(index),
+        &mut expected,
+    );
+    let c_result = generate_ckb_tx_message_all_from_c_header(&mock_tx, ScriptOrIndex::Index(index));
+
+    match (rust_result, c_result) {
+        (Ok(()), Ok(actual)) => {
+            assert!(
+                expected == actual,
+                "preimage mismatch, Rust: {} bytes, C: {} bytes",
+                expected.len(),
+                actual.len()
+            );
+            true
+        }
+        (Err(_), Err(_)) => false,
+        (rust_result, c_result) => panic!(
+            "Divergence, Rust: {:?}, C: {:?}",
+            rust_result.map(|_| expected.len()),
+            c_result.map(|actual| actual.len())
+        ),
+    }
+}
+
+/// Replaces the first witness of the script group via +f+
+fn _mutate_first_witness<F>(tx: TransactionView, index: usize, f: F) -> TransactionView
+where
+    F: FnOnce(&mut Vec<u8>),
+{
+    let mut witnesses: Vec<_> = tx.witnesses().into_iter().collect();
+    let mut witness = witnesses[index].raw_data().to_vec();
+    f(&mut witness);
+    let witness: Bytes = witness.into();
+    witnesses[index] = witness.pack();
+
+    tx.as_advanced_builder().set_witnesses(witnesses).build()
+}
+
+fn _test_valid_tx<F>(build: F, seed: u64)
+where
+    F: Fn(Bytes, Bytes, u64) -> (Context, TransactionView, Vec<usize>),
+{
+    let (contract_bin, always_success_bin) = _bins();
+    let (context, tx, indices) = build(contract_bin, always_success_bin, seed);
+
+    assert!(_compare(&context, &tx, indices[0]));
+}
+
+fn _test_invalid_witness(seed: u64) {
+    let (contract_bin, always_success_bin) = _bins();
+    let (context, tx, indices) = build_tx_with_witness_data(contract_bin, always_success_bin, seed);
+
+    // Same as invalid-witness-tx of the test vector generator
+    let mut rng = StableRng::seed_from_u64(seed.wrapping_add(1));
+    let tx = _mutate_first_witness(tx, indices[0], |witness| {
+        let byte_index = rng.gen_range(0..16);
+        let bit_index = rng.gen_range(0..8);
+        witness[byte_index] ^= 1 << bit_index;
+    });
+
+    assert!(!_compare(&context, &tx, indices[0]));
+}
+
+fn _test_mutated_witness(seed: u64) {
+    let (contract_bin, always_success_bin) = _bins();
+    let (context, tx, indices) = build_tx_with_witness_data(contract_bin, always_success_bin, seed);
+    let original_witness = tx.witnesses().get(indices[0]).unwrap().raw_data();
+    let original_preimage = _c_header_preimage(&context, &tx, indices[0]).expect("valid tx");
+
+    // Flip 1 - 3 distinct bytes, so the witness always changes
+    let mut rng = StableRng::seed_from_u64(seed.wrapping_add(1));
+    let tx = _mutate_first_witness(tx, indices[0], |witness| {
+        let amount = rng.gen_range(1..=3);
+        let mut positions: Vec<usize> = (0..witness.len()).collect();
+        rng.shuffle(&mut positions);
+        for i in positions.into_iter().take(amount) {
+            witness[i] ^= rng.gen_range(1..=u8::MAX as usize) as u8;
+        }
+    });
+    let mutated_witness = tx.witnesses().get(indices[0]).unwrap().raw_data();
+
+    let succeeded = _compare(&context, &tx, indices[0]);
+    match WitnessArgsReader::from_slice(&mutated_witness) {
+        Ok(mutated) => {
+            assert!(succeeded, "valid WitnessArgs is rejected");
+            // The lock field is not covered, the preimage changes iff other
+            // fields do
+            let original = WitnessArgsReader::from_slice(&original_witness).unwrap();
+            let fields_changed = original.input_type().as_slice()
+                != mutated.input_type().as_slice()
+                || original.output_type().as_slice() != mutated.output_type().as_slice();
+            let preimage = _c_header_preimage(&context, &tx, indices[0]).unwrap();
+            assert_eq!(preimage != original_preimage, fields_changed);
+        }
+        Err(_) => {
+            let error = _c_header_preimage(&context, &tx, indices[0]).unwrap_err();
+            assert!(
+                matches!(
+                    error,
+                    CHeaderError::Returned(
+                        MOL2_ERR_TOTAL_SIZE
+                            | MOL2_ERR_HEADER
+                            | MOL2_ERR_OFFSET
+                            | MOL2_ERR_FIELD_COUNT
+                            | MOL2_ERR_OVERFLOW
+                    ) | CHeaderError::Exited(MOL2_ERR_DATA)
+                ),
+                "unexpected error: {:?}",
+                error
+            );
+        }
+    }
+}
+
+fn _test_resized_witness(seed: u64) {
+    let (contract_bin, always_success_bin) = _bins();
+    let (context, tx, indices) = build_tx_with_witness_data(contract_bin, always_success_bin, seed);
+
+    let mut rng = StableRng::seed_from_u64(seed.wrapping_add(1));
+    let tx = _mutate_first_witness(tx, indices[0], |witness| {
+        if rng.next_u64() & 1 == 1 {
+            witness.truncate(rng.gen_range(0..witness.len()));
+        } else {
+            let mut extra = vec![0u8; rng.gen_range(1..8)];
+            rng.fill(&mut extra);
+            witness.extend(extra);
+        }
+    });
+
+    assert!(!_compare(&context, &tx, indices[0]));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_bare_tx(seed: u64) {
+        _test_valid_tx(build_bare_tx, seed);
+    }
+
+    #[test]
+    fn test_multiple_input_tx(seed: u64) {
+        _test_valid_tx(build_bare_tx_multiple_input_cells, seed);
+    }
+
+    #[test]
+    fn test_witness_tx(seed: u64) {
+        _test_valid_tx(build_tx_with_witness_data, seed);
+    }
+
+    #[test]
+    fn test_large_data_tx(seed: u64) {
+        _test_valid_tx(build_tx_with_super_large_data, seed);
+    }
+
+    #[test]
+    fn test_invalid_witness_tx(seed: u64) {
+        _test_invalid_witness(seed);
+    }
+
+    #[test]
+    fn test_mutated_witness_tx(seed: u64) {
+        _test_mutated_witness(seed);
+    }
+
+    #[test]
+    fn test_resized_witness_tx(seed: u64) {
+        _test_resized_witness(seed);
+    }
+}
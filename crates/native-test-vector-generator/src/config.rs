@@ -0,0 +1,146 @@
+use crate::Mode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use test_utils::TxShape;
+
+/// Generation parameters, loaded from a TOML or JSON file via --config.
+/// Sections missing from the file use the default profile, which matches
+/// the shapes built into test-utils. A shape section present in the file
+/// replaces the default shape of the mode entirely.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Number of vectors of each mode in a batch
+    pub counts: Counts,
+    pub bare: TxShape,
+    pub multiple_input: TxShape,
+    pub witness: TxShape,
+    /// Shape of the tx before its first group witness is corrupted
+    pub invalid_witness: TxShape,
+    pub large_data: TxShape,
+    /// Shape of the tx, group related fields apply to each script group
+    pub multi_group: TxShape,
+    /// Shape of the tx before witnesses are dropped, shared by modes with
+    /// sparse witnesses
+    pub sparse_witness: TxShape,
+    pub witness_args: TxShape,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            counts: Counts::default(),
+            bare: TxShape::bare(),
+            multiple_input: TxShape::multiple_input(),
+            witness: TxShape::witness(),
+            invalid_witness: TxShape::witness(),
+            large_data: TxShape::large_data(),
+            multi_group: TxShape::witness(),
+            sparse_witness: TxShape::witness(),
+            witness_args: TxShape::witness(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Counts {
+    pub bare: usize,
+    pub multiple_input: usize,
+    pub witness: usize,
+    pub invalid_witness: usize,
+    pub large_data: usize,
+    /// Vectors of modes added after the batch was settled are left out of a
+    /// batch by default, so batches recorded before they were introduced can
+    /// still be reproduced
+    pub multi_group: usize,
+    pub missing_later_witnesses: usize,
+    pub fewer_witnesses: usize,
+    pub first_group_input_last: usize,
+    pub missing_first_witness: usize,
+    /// Number of seeds, each leads to a vector per combination of
+    /// input_type & output_type
+    pub witness_args: usize,
+}
+
+impl Default for Counts {
+    fn default() -> Self {
+        Counts {
+            bare: 5,
+            multiple_input: 5,
+            witness: 10,
+            invalid_witness: 5,
+            large_data: 3,
+            multi_group: 0,
+            missing_later_witnesses: 0,
+            fewer_witnesses: 0,
+            first_group_input_last: 0,
+            missing_first_witness: 0,
+            witness_args: 0,
+        }
+    }
+}
+
+impl Config {
+    /// Loads a config file, which is parsed as TOML when the file name ends
+    /// with .toml, and as JSON otherwise
+    pub fn load(path: &str) -> Self {
+        let content = fs::read_to_string(path).expect("read config");
+        let config: Config = if Path::new(path).extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&content).expect("parse toml config")
+        } else {
+            serde_json::from_str(&content).expect("parse json config")
+        };
+        for mode in Mode::BATCH {
+            if let Err(e) = config.shape(mode).validate() {
+                panic!("Invalid shape of {:?} in {}: {}", mode, path, e);
+            }
+        }
+        // Data fields of witness-args vectors shall differ from empty ones
+        if *config.witness_args.witness_length.start() == 0 {
+            panic!(
+                "Invalid shape of {:?} in {}: witness_length shall start from at least 1",
+                Mode::WitnessArgs,
+                path
+            );
+        }
+        config
+    }
+
+    pub fn shape(&self, mode: Mode) -> &TxShape {
+        match mode {
+            Mode::All => unreachable!("all denotes a batch of vectors"),
+            Mode::Bare => &self.bare,
+            Mode::MultipleInput => &self.multiple_input,
+            Mode::Witness => &self.witness,
+            Mode::InvalidWitness => &self.invalid_witness,
+            Mode::LargeData => &self.large_data,
+            Mode::MultiGroup => &self.multi_group,
+            Mode::MissingLaterWitnesses
+            | Mode::FewerWitnesses
+            | Mode::FirstGroupInputLast
+            | Mode::MissingFirstWitness => &self.sparse_witness,
+            Mode::WitnessArgs => &self.witness_args,
+        }
+    }
+
+    /// Number of seeds of +mode+ in a batch, each seed leads to a single
+    /// vector except for witness-args mode
+    pub fn count(&self, mode: Mode) -> usize {
+        match mode {
+            Mode::All => unreachable!("all denotes a batch of vectors"),
+            Mode::Bare => self.counts.bare,
+            Mode::MultipleInput => self.counts.multiple_input,
+            Mode::Witness => self.counts.witness,
+            Mode::InvalidWitness => self.counts.invalid_witness,
+            Mode::LargeData => self.counts.large_data,
+            Mode::MultiGroup => self.counts.multi_group,
+            Mode::MissingLaterWitnesses => self.counts.missing_later_witnesses,
+            Mode::FewerWitnesses => self.counts.fewer_witnesses,
+            Mode::FirstGroupInputLast => self.counts.first_group_input_last,
+            Mode::MissingFirstWitness => self.counts.missing_first_witness,
+            Mode::WitnessArgs => self.counts.witness_args,
+        }
+    }
+}
@@ -1,15 +1,31 @@
+use ckb_mock_tx_types::{MockTransaction, ReprMockTransaction};
 use ckb_testtool::{
     ckb_types::{bytes::Bytes, core::TransactionView, prelude::*},
     context::Context,
 };
-use ckb_tx_message_all_utils::ckb_tx_message_all_from_mock_tx::{
-    generate_ckb_tx_message_all_from_mock_tx, ScriptOrIndex,
+use ckb_tx_message_all_utils::{
+    ckb_tx_message_all_from_mock_tx::{
+        generate_ckb_tx_message_all_from_mock_tx_with_version,
+        generate_ckb_tx_message_from_mock_tx, ScriptOrIndex,
+    },
+    fixture::Fixture,
+    message_version::MessageVersion,
 };
-use clap::{Parser, ValueEnum};
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use clap::{Parser, Subcommand, ValueEnum};
+use config::*;
+use manifest::*;
+use reproduce::*;
 use std::fs;
 use std::path::Path;
 use test_utils::*;
+use verification::*;
+use verify_dir::*;
+
+mod config;
+mod manifest;
+mod reproduce;
+mod verification;
+mod verify_dir;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 enum Mode {
@@ -25,11 +41,123 @@ enum Mode {
     LargeData,
     /// Generate invalid tx with first witness that is not WitnessArgs
     InvalidWitness,
+    /// Generate tx with one script group per contract, plus one more group
+    /// of the first contract with different args, each signed independently
+    MultiGroup,
+    /// Generate tx whose input cells in current script group have no witness
+    /// except the first one
+    MissingLaterWitnesses,
+    /// Generate tx with fewer witnesses than input cells, at least the last
+    /// input cell in current script group has no witness
+    FewerWitnesses,
+    /// Generate tx whose only input cell in current script group is the last
+    /// input cell
+    FirstGroupInputLast,
+    /// Generate invalid tx with no witness for the first input cell in
+    /// current script group
+    MissingFirstWitness,
+    /// Generate a tx per combination of None, empty & non-empty input_type &
+    /// output_type in the first witness of current script group, sharing
+    /// everything else
+    WitnessArgs,
+}
+
+impl Mode {
+    /// Modes of vectors in a batch, in generation order
+    const BATCH: [Mode; 11] = [
+        Mode::Bare,
+        Mode::MultipleInput,
+        Mode::Witness,
+        Mode::InvalidWitness,
+        Mode::LargeData,
+        Mode::MultiGroup,
+        Mode::MissingLaterWitnesses,
+        Mode::FewerWitnesses,
+        Mode::FirstGroupInputLast,
+        Mode::MissingFirstWitness,
+        Mode::WitnessArgs,
+    ];
+}
+
+/// Name of a value as accepted on the command line, which is also kept in
+/// the manifest
+trait ValueName: ValueEnum {
+    fn name(&self) -> String {
+        self.to_possible_value()
+            .expect("value")
+            .get_name()
+            .to_string()
+    }
+}
+
+impl<T: ValueEnum> ValueName for T {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Algorithm {
+    /// CKB flavored blake2b, no algorithm id is kept in script args
+    Blake2b,
+    /// SHA-256
+    Sha256,
+    /// Keccak-256
+    Keccak256,
+}
+
+impl From<Algorithm> for HashAlgorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Blake2b => HashAlgorithm::CkbBlake2b,
+            Algorithm::Sha256 => HashAlgorithm::Sha256,
+            Algorithm::Keccak256 => HashAlgorithm::Keccak256,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Sighash {
+    /// CKB_TX_MESSAGE_ALL, no sighash mode id is kept in script args
+    All,
+    /// Commits to input cells of the group & output cells of the same indices
+    SingleGroupOutputs,
+    /// Commits to input cells of the group & all output cells
+    AnyoneCanPay,
+}
+
+impl From<Sighash> for SighashMode {
+    fn from(sighash: Sighash) -> Self {
+        match sighash {
+            Sighash::All => SighashMode::All,
+            Sighash::SingleGroupOutputs => SighashMode::SingleGroupOutputs,
+            Sighash::AnyoneCanPay => SighashMode::AnyoneCanPay,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Version {
+    /// The original CKB_TX_MESSAGE_ALL specification
+    V1,
+}
+
+impl From<Version> for MessageVersion {
+    fn from(version: Version) -> Self {
+        match version {
+            Version::V1 => MessageVersion::V1,
+        }
+    }
 }
 
 #[derive(Debug, Parser)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Generation mode
     #[arg(long, value_enum, default_value_t = Mode::All)]
     mode: Mode,
@@ -38,21 +166,112 @@ struct Cli {
     #[arg(long)]
     seed: Option<u64>,
 
-    /// Output folder
-    #[arg(long)]
-    output: String,
+    /// Output folder, required unless a subcommand is given
+    #[arg(long, required = true)]
+    output: Option<String>,
+
+    /// CKB_TX_MESSAGE contracts, each vector is verified against all of
+    /// them. The first one is used as the lock when building vectors.
+    #[arg(
+        long = "contract",
+        default_values = [
+            "./build/release/rust-assert-ckb-tx-message-all",
+            "./build/release/c-assert-ckb-tx-message-all",
+        ]
+    )]
+    contracts: Vec<String>,
+
+    /// Hash algorithm used to digest CKB_TX_MESSAGE_ALL, the algorithm id is
+    /// kept as the first byte of lock script args for non-default algorithms
+    #[arg(long, value_enum, default_value_t = Algorithm::Blake2b)]
+    hash_algorithm: Algorithm,
 
-    /// CKB_TX_MESSAGE contract
-    #[arg(long, default_value = "./build/release/rust-assert-ckb-tx-message-all")]
-    contract: String,
+    /// Revision of the CKB_TX_MESSAGE_ALL specification used to generate
+    /// hashes, which is kept in the .version file of each test case
+    #[arg(long, value_enum, default_value_t = Version::V1)]
+    message_version: Version,
+
+    /// Experimental sighash mode of the message, the mode id is kept as the
+    /// second byte of lock script args for modes other than all
+    #[arg(long, value_enum, default_value_t = Sighash::All)]
+    sighash_mode: Sighash,
 
     /// Always success contract
     #[arg(long, default_value = "./build/release/always-success")]
     always_success: String,
+
+    /// TOML(.toml) or JSON config file, setting shapes of transactions &
+    /// number of vectors of each mode in a batch
+    #[arg(long)]
+    config: Option<String>,
+}
+
+impl Cli {
+    fn output(&self) -> &str {
+        self.output.as_deref().expect("output folder")
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Verify vectors in an existing output folder
+    Verify(VerifyArgs),
+    /// Regenerate vectors in an existing output folder from seeds recorded
+    /// in its manifest, reporting any difference
+    Reproduce(ReproduceArgs),
+    /// Print the default config in TOML, as a template of --config
+    DefaultConfig,
+}
+
+#[derive(Debug, clap::Args)]
+struct VerifyArgs {
+    /// Folder containing vectors
+    #[arg(long)]
+    dir: String,
+
+    /// CKB_TX_MESSAGE contracts to replay each vector with, vectors are not
+    /// replayed when none is given
+    #[arg(long = "contract")]
+    contracts: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+struct ReproduceArgs {
+    /// Folder containing vectors & the manifest
+    #[arg(long)]
+    dir: String,
+
+    /// CKB_TX_MESSAGE contracts used to build vectors, defaults to the ones
+    /// recorded in the manifest
+    #[arg(long = "contract")]
+    contracts: Vec<String>,
+
+    /// Always success contract, defaults to the one recorded in the manifest
+    #[arg(long)]
+    always_success: Option<String>,
 }
 
 fn main() {
     let cli = Cli::parse();
+    match &cli.command {
+        Some(Command::Verify(args)) => {
+            if !verify_dir(args) {
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(Command::Reproduce(args)) => {
+            if !reproduce_dir(args) {
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(Command::DefaultConfig) => {
+            print!("{}", toml::to_string(&Config::default()).expect("to toml"));
+            return;
+        }
+        None => (),
+    }
 
     let seed = match cli.seed {
         Some(seed) => seed,
@@ -63,140 +282,416 @@ fn main() {
     };
     println!("Seed: {}", seed);
 
-    fs::create_dir_all(&cli.output).expect("mkdir");
+    let config = cli.config.as_deref().map(Config::load).unwrap_or_default();
+    fs::create_dir_all(cli.output()).expect("mkdir");
+    let binaries = Binaries::load(&cli);
+
+    let vectors = planned_vectors(&config, cli.mode, seed)
+        .into_iter()
+        .map(|plan| save_vector(&cli, &binaries, &config, &plan))
+        .collect();
+
+    let manifest = Manifest {
+        generator_version: env!("CARGO_PKG_VERSION").to_string(),
+        mode: cli.mode.name(),
+        seed,
+        hash_algorithm: cli.hash_algorithm.name(),
+        message_version: MessageVersion::from(cli.message_version).into(),
+        sighash_mode: cli.sighash_mode.name(),
+        contracts: cli
+            .contracts
+            .iter()
+            .zip(&binaries.contracts)
+            .map(|(path, binary)| Binary::new(path, binary))
+            .collect(),
+        always_success: Binary::new(&cli.always_success, &binaries.always_success),
+        config,
+        vectors,
+    };
+    fs::write(
+        Path::new(cli.output()).join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest).expect("to json"),
+    )
+    .expect("write manifest");
+}
+
+/// A vector to generate
+struct Plan {
+    mode: Mode,
+    seed: u64,
+    batch: Option<usize>,
+    /// Fields of the first witness in current script group, only for
+    /// witness-args mode
+    witness_args: Option<WitnessArgsFields>,
+}
 
-    match cli.mode {
+/// Vectors generated from +seed+. In a batch, the seed of each vector is
+/// drawn from +seed+. A seed of witness-args mode leads to a vector per
+/// combination of input_type & output_type, all of them signed.
+fn planned_vectors(config: &Config, mode: Mode, seed: u64) -> Vec<Plan> {
+    let seeds = match mode {
         Mode::All => {
-            let mut rng = StdRng::seed_from_u64(seed);
+            let mut rng = StableRng::seed_from_u64(seed);
+            let mut seeds = vec![];
 
-            for i in 1..=5 {
-                save_bare_tx(&cli, rng.gen(), &format!("-batch{}", i));
-            }
-            for i in 1..=5 {
-                save_multiple_input_tx(&cli, rng.gen(), &format!("-batch{}", i));
+            for mode in Mode::BATCH {
+                for i in 1..=config.count(mode) {
+                    seeds.push((mode, rng.next_u64(), Some(i)));
+                }
             }
-            for i in 1..=10 {
-                save_witness_tx(&cli, rng.gen(), &format!("-batch{}", i));
-            }
-            for i in 1..=5 {
-                save_invalid_witness_tx(&cli, rng.gen(), &format!("-batch{}", i));
-            }
-            for i in 1..=3 {
-                save_large_data_tx(&cli, rng.gen(), &format!("-batch{}", i));
-            }
-        }
-        Mode::Bare => {
-            save_bare_tx(&cli, seed, &format!("-from-seed-{}", seed));
+            seeds
         }
-        Mode::MultipleInput => {
-            save_multiple_input_tx(&cli, seed, &format!("-from-seed-{}", seed));
-        }
-        Mode::Witness => {
-            save_witness_tx(&cli, seed, &format!("-from-seed-{}", seed));
-        }
-        Mode::LargeData => {
-            save_large_data_tx(&cli, seed, &format!("-from-seed-{}", seed));
+        mode => vec![(mode, seed, None)],
+    };
+
+    let mut vectors = vec![];
+    for (mode, seed, batch) in seeds {
+        if mode != Mode::WitnessArgs {
+            vectors.push(Plan {
+                mode,
+                seed,
+                batch,
+                witness_args: None,
+            });
+            continue;
         }
-        Mode::InvalidWitness => {
-            save_invalid_witness_tx(&cli, seed, &format!("-from-seed-{}", seed));
+        for input_type in FieldContent::ALL {
+            for output_type in FieldContent::ALL {
+                vectors.push(Plan {
+                    mode,
+                    seed,
+                    batch,
+                    witness_args: Some(WitnessArgsFields {
+                        lock: FieldContent::Data,
+                        input_type,
+                        output_type,
+                    }),
+                });
+            }
         }
     }
+    vectors
 }
 
-fn save_bare_tx(cli: &Cli, seed: u64, suffix: &str) {
-    let contract_bin: Bytes = fs::read(&cli.contract).expect("read").into();
-    let always_success_bin: Bytes = fs::read(&cli.always_success).expect("read").into();
-    let path = Path::new(&cli.output).join(format!("bare-tx{}", suffix));
+fn vector_name(plan: &Plan) -> String {
+    let name = match plan.batch {
+        Some(batch) => format!("{}-tx-batch{}", plan.mode.name(), batch),
+        None => format!("{}-tx-from-seed-{}", plan.mode.name(), plan.seed),
+    };
+    match plan.witness_args {
+        Some(fields) => format!(
+            "{}-input-type-{}-output-type-{}",
+            name,
+            field_content_name(fields.input_type),
+            field_content_name(fields.output_type)
+        ),
+        None => name,
+    }
+}
 
-    let (context, tx, indices) = build_bare_tx(contract_bin, always_success_bin, seed);
-    save_tx(context, tx, indices, &path);
+fn field_content_name(content: FieldContent) -> &'static str {
+    match content {
+        FieldContent::None => "none",
+        FieldContent::Empty => "empty",
+        FieldContent::Data => "data",
+    }
 }
 
-fn save_multiple_input_tx(cli: &Cli, seed: u64, suffix: &str) {
-    let contract_bin: Bytes = fs::read(&cli.contract).expect("read").into();
-    let always_success_bin: Bytes = fs::read(&cli.always_success).expect("read").into();
-    let path = Path::new(&cli.output).join(format!("multiple-input-tx{}", suffix));
+/// Contract binaries used to build vectors, loaded only once
+struct Binaries {
+    contracts: Vec<Bytes>,
+    always_success: Bytes,
+}
 
-    let (context, tx, indices) =
-        build_bare_tx_multiple_input_cells(contract_bin, always_success_bin, seed);
-    save_tx(context, tx, indices, &path);
+impl Binaries {
+    fn load(cli: &Cli) -> Self {
+        Binaries {
+            contracts: cli
+                .contracts
+                .iter()
+                .map(|path| fs::read(path).expect("read").into())
+                .collect(),
+            always_success: fs::read(&cli.always_success).expect("read").into(),
+        }
+    }
 }
 
-fn save_witness_tx(cli: &Cli, seed: u64, suffix: &str) {
-    let contract_bin: Bytes = fs::read(&cli.contract).expect("read").into();
-    let always_success_bin: Bytes = fs::read(&cli.always_success).expect("read").into();
-    let path = Path::new(&cli.output).join(format!("witness-tx{}", suffix));
+/// Builds the vector planned by +plan+ and verifies it against all
+/// contracts, then saves it to the output folder. The vector is named after
+/// +batch+ in a batch, or after +seed+ otherwise.
+///
+/// The generator exits without saving the vector, when any contract
+/// disagrees with the expected outcome: a vector with hashes of all script
+/// groups shall pass all contracts, otherwise it shall fail all of them.
+fn save_vector(cli: &Cli, binaries: &Binaries, config: &Config, plan: &Plan) -> Vector {
+    let name = vector_name(plan);
+    let (mut context, tx, groups) = build_vector(cli, binaries, config, plan);
+    let mock_tx = context.dump_tx(&tx).expect("dump tx");
+    let groups = group_outcomes(cli, &mock_tx.clone().into(), groups);
+
+    let verifications = verify_vector(
+        &mut context,
+        &tx,
+        groups[0].indices[0],
+        cli.contracts.iter().zip(&binaries.contracts),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Vector {} is not saved, cannot be verified: {}", name, e);
+        std::process::exit(1);
+    });
+    let passing = groups
+        .iter()
+        .all(|group| matches!(group.expected, Expected::Hash { .. }));
+    for verification in &verifications {
+        if verification.passed != passing {
+            eprintln!(
+                "Vector {} is not saved, expected {:?}, got {:?}",
+                name, groups, verification
+            );
+            std::process::exit(1);
+        }
+    }
+
+    save_tx(
+        &mock_tx,
+        &groups,
+        cli.message_version.into(),
+        cli.sighash_mode.into(),
+        &Path::new(cli.output()).join(&name),
+    );
+
+    Vector {
+        name,
+        mode: plan.mode.name(),
+        seed: plan.seed,
+        batch: plan.batch,
+        witness_args: plan.witness_args,
+        indices: groups[0].indices.clone(),
+        expected: groups[0].expected.clone(),
+        groups: if groups.len() > 1 { groups } else { vec![] },
+        verifications,
+    }
+}
 
-    let (context, tx, indices) = build_tx_with_witness_data(contract_bin, always_success_bin, seed);
-    save_tx(context, tx, indices, &path);
+/// Expected outcome of each script group, the first group is the one of
+/// single group modes
+fn group_outcomes(cli: &Cli, mock_tx: &MockTransaction, groups: Vec<Vec<usize>>) -> Vec<Group> {
+    groups
+        .into_iter()
+        .map(|indices| Group {
+            expected: expected_outcome(
+                mock_tx,
+                &indices,
+                cli.hash_algorithm.into(),
+                cli.message_version.into(),
+                cli.sighash_mode.into(),
+            ),
+            indices,
+        })
+        .collect()
 }
 
-fn save_invalid_witness_tx(cli: &Cli, seed: u64, suffix: &str) {
-    let contract_bin: Bytes = fs::read(&cli.contract).expect("read").into();
-    let always_success_bin: Bytes = fs::read(&cli.always_success).expect("read").into();
-    let path = Path::new(&cli.output).join(format!("invalid-witness-tx{}", suffix));
+fn build_vector(
+    cli: &Cli,
+    binaries: &Binaries,
+    config: &Config,
+    plan: &Plan,
+) -> (Context, TransactionView, Vec<Vec<usize>>) {
+    let lock = lock_setup(cli, binaries.contracts[0].clone());
+    let always_success_bin = binaries.always_success.clone();
+    let Plan { mode, seed, .. } = *plan;
+    let shape = config.shape(mode);
 
-    let (context, tx, indices) = build_tx_with_witness_data(contract_bin, always_success_bin, seed);
+    let (context, tx, indices) = match mode {
+        Mode::All => unreachable!("all denotes a batch of vectors"),
+        Mode::MultiGroup => {
+            return build_multi_group_tx_with_shape(
+                multi_group_locks(cli, binaries),
+                always_success_bin,
+                shape,
+                seed,
+            )
+        }
+        Mode::Bare | Mode::MultipleInput => {
+            build_bare_tx_with_shape(lock, always_success_bin, shape, seed)
+        }
+        Mode::Witness => {
+            build_tx_with_witness_data_with_shape(lock, always_success_bin, shape, seed)
+        }
+        Mode::LargeData => {
+            build_tx_with_super_large_data_with_shape(lock, always_success_bin, shape, seed)
+        }
+        Mode::MissingLaterWitnesses => build_tx_with_sparse_witnesses_with_shape(
+            lock,
+            always_success_bin,
+            SparseWitnesses::MissingLaterGroupWitnesses,
+            shape,
+            seed,
+        ),
+        Mode::FewerWitnesses => build_tx_with_sparse_witnesses_with_shape(
+            lock,
+            always_success_bin,
+            SparseWitnesses::FewerWitnessesThanInputs,
+            shape,
+            seed,
+        ),
+        Mode::FirstGroupInputLast => build_tx_with_sparse_witnesses_with_shape(
+            lock,
+            always_success_bin,
+            SparseWitnesses::FirstGroupInputLast,
+            shape,
+            seed,
+        ),
+        Mode::MissingFirstWitness => build_tx_with_sparse_witnesses_with_shape(
+            lock,
+            always_success_bin,
+            SparseWitnesses::MissingFirstGroupWitness,
+            shape,
+            seed,
+        ),
+        Mode::WitnessArgs => build_tx_with_witness_args_fields_with_shape(
+            lock,
+            always_success_bin,
+            plan.witness_args.expect("witness args fields"),
+            shape,
+            seed,
+        ),
+        Mode::InvalidWitness => {
+            let (context, tx, indices) =
+                build_tx_with_witness_data_with_shape(lock, always_success_bin, shape, seed);
+
+            // Flip one of the first 128 bits(16 bytes) of the last witness,
+            // which contains a WitnessArgs structure
+            let tx = {
+                let mut witnesses: Vec<_> = tx.witnesses().into_iter().collect();
+                let mut last_witness = witnesses[indices[0]].raw_data().to_vec();
 
-    // Flip one of the first 128 bits(16 bytes) of the last witness,
-    // which contains a WitnessArgs structure
-    let tx = {
-        let mut witnesses: Vec<_> = tx.witnesses().into_iter().collect();
-        let mut last_witness = witnesses[indices[0]].raw_data().to_vec();
+                let mut rng = StableRng::seed_from_u64(seed.wrapping_add(1));
+                let byte_index = rng.gen_range(0..16);
+                let bit_index = rng.gen_range(0..8);
 
-        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(1));
-        let byte_index = rng.gen_range(0..16);
-        let bit_index = rng.gen_range(0..8);
+                last_witness[byte_index] ^= 1 << bit_index;
 
-        last_witness[byte_index] ^= 1 << bit_index;
+                let last_witness: Bytes = last_witness.into();
+                witnesses[indices[0]] = last_witness.pack();
 
-        let last_witness: Bytes = last_witness.into();
-        witnesses[indices[0]] = last_witness.pack();
+                tx.as_advanced_builder().set_witnesses(witnesses).build()
+            };
 
-        tx.as_advanced_builder().set_witnesses(witnesses).build()
+            (context, tx, indices)
+        }
     };
 
-    save_tx(context, tx, indices, &path);
+    (context, tx, vec![indices])
 }
 
-fn save_large_data_tx(cli: &Cli, seed: u64, suffix: &str) {
-    let contract_bin: Bytes = fs::read(&cli.contract).expect("read").into();
-    let always_success_bin: Bytes = fs::read(&cli.always_success).expect("read").into();
-    let path = Path::new(&cli.output).join(format!("large-data-tx{}", suffix));
+fn lock_setup(cli: &Cli, contract_bin: Bytes) -> LockSetup {
+    let algorithm: HashAlgorithm = cli.hash_algorithm.into();
+    let mode: SighashMode = cli.sighash_mode.into();
+    let lock = LockSetup::new(contract_bin)
+        .signer(hash_signer(algorithm))
+        .sighash_mode(mode);
+    match (algorithm, mode) {
+        (HashAlgorithm::CkbBlake2b, SighashMode::All) => lock,
+        (_, SighashMode::All) => lock.args(vec![algorithm as u8].into()),
+        _ => lock.args(vec![algorithm as u8, mode.into()].into()),
+    }
+}
+
+/// One lock per contract, with the same args as single group modes, so
+/// different contracts are side by side in a tx. One more lock of the first
+/// contract follows, its args spell out both the algorithm & sighash mode
+/// ids, plus a trailing byte ignored by contracts.
+fn multi_group_locks(cli: &Cli, binaries: &Binaries) -> Vec<LockSetup> {
+    let algorithm: HashAlgorithm = cli.hash_algorithm.into();
+    let mode: SighashMode = cli.sighash_mode.into();
+    let mut locks: Vec<_> = binaries
+        .contracts
+        .iter()
+        .map(|contract_bin| lock_setup(cli, contract_bin.clone()))
+        .collect();
+    locks.push(
+        lock_setup(cli, binaries.contracts[0].clone())
+            .args(vec![algorithm as u8, mode.into(), 1].into()),
+    );
+    locks
+}
 
-    let (context, tx, indices) =
-        build_tx_with_super_large_data(contract_bin, always_success_bin, seed);
-    save_tx(context, tx, indices, &path);
+/// Generates the message hash, or the reason why it cannot be generated
+fn expected_outcome(
+    mock_tx: &MockTransaction,
+    indices: &[usize],
+    algorithm: HashAlgorithm,
+    version: MessageVersion,
+    mode: SighashMode,
+) -> Expected {
+    let mut hasher = Hasher::new(algorithm);
+    let result = match mode {
+        SighashMode::All => generate_ckb_tx_message_all_from_mock_tx_with_version(
+            mock_tx,
+            ScriptOrIndex::Index(indices[0]),
+            version,
+            &mut hasher,
+        ),
+        mode => generate_ckb_tx_message_from_mock_tx(
+            mock_tx,
+            ScriptOrIndex::Index(indices[0]),
+            mode,
+            &mut hasher,
+        ),
+    };
+    match result {
+        Ok(()) => {
+            let hash: Bytes = hasher.hash().to_vec().into();
+            Expected::Hash {
+                hash: format!("{:x}", hash),
+            }
+        }
+        Err(e) => Expected::Failure {
+            reason: e.reason().to_string(),
+        },
+    }
 }
 
-fn save_tx(context: Context, tx: TransactionView, indices: Vec<usize>, path: &Path) {
+fn save_tx(
+    mock_tx: &ReprMockTransaction,
+    groups: &[Group],
+    version: MessageVersion,
+    mode: SighashMode,
+    path: &Path,
+) {
     let path = path.to_str().expect("os str");
+    let Group { indices, expected } = &groups[0];
 
-    let mock_tx = context.dump_tx(&tx).expect("dump tx");
     // Save tx file
     fs::write(
         format!("{}.json", path),
-        serde_json::to_string_pretty(&mock_tx).expect("to json"),
+        serde_json::to_string_pretty(mock_tx).expect("to json"),
     )
     .expect("write tx file");
     // Save indices
     fs::write(
         format!("{}.indices", path),
-        serde_json::to_string_pretty(&indices).expect("to json"),
+        serde_json::to_string_pretty(indices).expect("to json"),
     )
     .expect("write index file");
+    // Save message version
+    fs::write(format!("{}.version", path), u8::from(version).to_string())
+        .expect("write version file");
     // Save message if possible
-    {
-        let mut hasher = Hasher::default();
-        if generate_ckb_tx_message_all_from_mock_tx(
-            &mock_tx.clone().into(),
-            ScriptOrIndex::Index(indices[0]),
-            &mut hasher,
+    if let Expected::Hash { hash } = expected {
+        fs::write(format!("{}.hash", path), hash).expect("write hash");
+    }
+    // Save indices & hashes of all script groups for multi-group vectors
+    if groups.len() > 1 {
+        fs::write(
+            format!("{}.groups", path),
+            serde_json::to_string_pretty(groups).expect("to json"),
         )
-        .is_ok()
-        {
-            let hash: Bytes = hasher.hash().to_vec().into();
-            fs::write(format!("{}.hash", path), format!("{:x}", hash)).expect("write hash");
-        }
+        .expect("write groups file");
     }
+    // Save self-contained fixture
+    let fixture = Fixture::generate_from_mock_tx(&mock_tx.clone().into(), indices, version, mode)
+        .expect("generate fixture");
+    fs::write(format!("{}.fixture", path), fixture.to_json()).expect("write fixture");
 }
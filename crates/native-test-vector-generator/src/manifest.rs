@@ -0,0 +1,174 @@
+use crate::Config;
+use ckb_testtool::ckb_types::bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use test_utils::{HashAlgorithm, Hasher, WitnessArgsFields};
+
+/// File name of the manifest in the output folder
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Lists every vector in the output folder, so consumers can iterate vectors
+/// without parsing file names
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub generator_version: String,
+    /// Generation mode passed to the generator
+    pub mode: String,
+    /// Seed passed to the generator, or picked from current time
+    #[serde(with = "seed")]
+    pub seed: u64,
+    pub hash_algorithm: String,
+    pub message_version: u8,
+    pub sighash_mode: String,
+    /// CKB_TX_MESSAGE_ALL contracts used as the lock of current script group
+    pub contracts: Vec<Binary>,
+    pub always_success: Binary,
+    /// Generation parameters, manifests written before configs were
+    /// introduced use the default profile
+    #[serde(default)]
+    pub config: Config,
+    pub vectors: Vec<Vector>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Binary {
+    pub path: String,
+    /// SHA-256 of the binary in hex notation
+    pub sha256: String,
+}
+
+impl Binary {
+    pub fn new(path: &str, binary: &Bytes) -> Self {
+        let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+        hasher.update(binary);
+        let sha256: Bytes = hasher.hash().to_vec().into();
+
+        Binary {
+            path: path.to_string(),
+            sha256: format!("{:x}", sha256),
+        }
+    }
+}
+
+/// A vector, its files share +name+ as the file name, with different
+/// extensions
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Vector {
+    pub name: String,
+    pub mode: String,
+    /// Seed used to build the vector alone
+    #[serde(with = "seed")]
+    pub seed: u64,
+    /// Index in current batch, starting from 1. None when a single vector
+    /// is generated.
+    pub batch: Option<usize>,
+    /// Fields of the first witness in current script group, only present
+    /// for witness-args vectors, which share the seed with vectors of other
+    /// fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness_args: Option<WitnessArgsFields>,
+    /// Indices of input cells in current script group
+    pub indices: Vec<usize>,
+    pub expected: Expected,
+    /// Every CKB_TX_MESSAGE_ALL validating script group of multi-group
+    /// vectors, as kept in the .groups file. The first group is the one
+    /// described by +indices+ & +expected+. Empty for single group vectors.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+    /// Outcomes of running the vector with each of the contracts
+    pub verifications: Vec<Verification>,
+}
+
+/// A CKB_TX_MESSAGE_ALL validating script group in a vector
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Group {
+    /// Indices of input cells in the script group
+    pub indices: Vec<usize>,
+    pub expected: Expected,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum Expected {
+    /// The message hash in hex notation, also kept in the .hash file
+    Hash { hash: String },
+    /// Message generation fails, no .hash file is saved
+    Failure { reason: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Verification {
+    /// Path of the contract binary, same as the one in contracts
+    pub contract: String,
+    pub passed: bool,
+    /// Exit code of the failing script, None when the vector passes, or
+    /// when verification fails for other reasons
+    pub error_code: Option<i8>,
+    /// Consumed cycles, None when the vector fails
+    pub cycles: Option<u64>,
+}
+
+/// Seeds are kept as decimal strings, since JSON consumers commonly parse
+/// numbers as f64, which cannot hold every u64. Manifests written before
+/// keep seeds as numbers, which are still accepted.
+mod seed {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&seed.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Seed {
+            String(String),
+            Number(u64),
+        }
+
+        match Seed::deserialize(deserializer)? {
+            Seed::String(s) => s.parse().map_err(serde::de::Error::custom),
+            Seed::Number(n) => Ok(n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(seed: u64) -> Vector {
+        Vector {
+            name: "bare-tx-batch1".to_string(),
+            mode: "bare".to_string(),
+            seed,
+            batch: Some(1),
+            witness_args: None,
+            indices: vec![0, 2],
+            expected: Expected::Failure {
+                reason: "invalid_witness_args".to_string(),
+            },
+            groups: vec![],
+            verifications: vec![],
+        }
+    }
+
+    #[test]
+    fn test_seeds_are_written_as_strings() {
+        // Above 2^53, where f64 loses precision
+        let seed = 12740027877540924609;
+        let json = serde_json::to_value(vector(seed)).unwrap();
+        assert_eq!(json["seed"], serde_json::json!("12740027877540924609"));
+
+        let loaded: Vector = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.seed, seed);
+    }
+
+    #[test]
+    fn test_numeric_seeds_are_still_read() {
+        let mut json = serde_json::to_value(vector(0)).unwrap();
+        json["seed"] = serde_json::json!(u64::MAX);
+
+        let loaded: Vector = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.seed, u64::MAX);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    build_vector, group_outcomes, planned_vectors, vector_name, Algorithm, Binaries, Binary, Cli,
+    Expected, Manifest, Mode, Plan, ReproduceArgs, Sighash, Version, MANIFEST_FILE,
+};
+use clap::ValueEnum;
+use std::fs;
+use std::path::Path;
+use test_utils::MessageVersion;
+
+/// Regenerates in memory every vector recorded in the manifest of
+/// +args.dir+, printing a report per vector. Returns true when all vectors
+/// are reproduced byte for byte.
+pub fn reproduce_dir(args: &ReproduceArgs) -> bool {
+    let dir = Path::new(&args.dir);
+    let manifest: Manifest =
+        serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE)).expect("read manifest"))
+            .expect("parse manifest");
+    let cli = cli_from_manifest(&manifest, args);
+    let binaries = Binaries::load(&cli);
+
+    // Vectors contain contract binaries, different binaries always lead to
+    // different vectors
+    let binary_pairs = manifest
+        .contracts
+        .iter()
+        .zip(cli.contracts.iter().zip(&binaries.contracts))
+        .chain([(
+            &manifest.always_success,
+            (&cli.always_success, &binaries.always_success),
+        )]);
+    let mut binaries_match = true;
+    for (recorded, (path, binary)) in binary_pairs {
+        let binary = Binary::new(path, binary);
+        if binary.sha256 != recorded.sha256 {
+            binaries_match = false;
+            eprintln!(
+                "Binary {} has SHA-256 {}, while vectors were generated with {}",
+                path, binary.sha256, recorded.sha256
+            );
+        }
+    }
+    if !binaries_match {
+        return false;
+    }
+
+    // Seeds of vectors in a batch shall be drawn from the recorded seed
+    let planned: Vec<(String, u64)> = planned_vectors(&manifest.config, cli.mode, manifest.seed)
+        .into_iter()
+        .map(|plan| (vector_name(&plan), plan.seed))
+        .collect();
+    let recorded: Vec<(String, u64)> = manifest
+        .vectors
+        .iter()
+        .map(|vector| (vector.name.clone(), vector.seed))
+        .collect();
+    let seeds_match = planned == recorded;
+    if !seeds_match {
+        println!("seeds: FAILED");
+        if planned.len() != recorded.len() {
+            println!(
+                "  {} vectors recorded, {} vectors planned",
+                recorded.len(),
+                planned.len()
+            );
+        }
+        for ((recorded_name, recorded_seed), (planned_name, planned_seed)) in
+            recorded.iter().zip(&planned)
+        {
+            if (recorded_name, recorded_seed) != (planned_name, planned_seed) {
+                println!(
+                    "  {} recorded with seed {}, while {} is planned with seed {}",
+                    recorded_name, recorded_seed, planned_name, planned_seed
+                );
+            }
+        }
+    }
+
+    let mut failed = 0;
+    for vector in &manifest.vectors {
+        let plan = Plan {
+            mode: Mode::from_str(&vector.mode, false).expect("mode"),
+            seed: vector.seed,
+            batch: vector.batch,
+            witness_args: vector.witness_args,
+        };
+        let (context, tx, groups) = build_vector(&cli, &binaries, &manifest.config, &plan);
+        let mock_tx = context.dump_tx(&tx).expect("dump tx");
+        let groups = group_outcomes(&cli, &mock_tx.clone().into(), groups);
+
+        let path = dir.join(&vector.name);
+        let path = path.to_str().expect("os str");
+        let mut problems: Vec<String> = [
+            compare_file(
+                &format!("{}.json", path),
+                serde_json::to_string_pretty(&mock_tx).expect("to json"),
+            ),
+            compare_file(
+                &format!("{}.indices", path),
+                serde_json::to_string_pretty(&groups[0].indices).expect("to json"),
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let hash_file = format!("{}.hash", path);
+        match &groups[0].expected {
+            Expected::Hash { hash } => problems.extend(compare_file(&hash_file, hash.clone())),
+            Expected::Failure { reason } => {
+                if Path::new(&hash_file).exists() {
+                    problems.push(format!(
+                        "{} exists, but regenerated vector has no hash: {}",
+                        hash_file, reason
+                    ));
+                }
+            }
+        }
+        if groups.len() > 1 {
+            problems.extend(compare_file(
+                &format!("{}.groups", path),
+                serde_json::to_string_pretty(&groups).expect("to json"),
+            ));
+        }
+
+        if problems.is_empty() {
+            println!("{}: ok", vector.name);
+        } else {
+            failed += 1;
+            println!("{}: FAILED", vector.name);
+            for problem in problems {
+                println!("  {}", problem);
+            }
+        }
+    }
+    println!(
+        "{} of {} vectors not reproduced",
+        failed,
+        manifest.vectors.len()
+    );
+    seeds_match && failed == 0
+}
+
+/// Generator arguments recorded in the manifest, binaries recorded in the
+/// manifest can be overridden by +args+
+fn cli_from_manifest(manifest: &Manifest, args: &ReproduceArgs) -> Cli {
+    let message_version = Version::value_variants()
+        .iter()
+        .copied()
+        .find(|version| u8::from(MessageVersion::from(*version)) == manifest.message_version)
+        .expect("message version");
+
+    Cli {
+        command: None,
+        mode: Mode::from_str(&manifest.mode, false).expect("mode"),
+        seed: Some(manifest.seed),
+        output: None,
+        contracts: if args.contracts.is_empty() {
+            manifest
+                .contracts
+                .iter()
+                .map(|binary| binary.path.clone())
+                .collect()
+        } else {
+            args.contracts.clone()
+        },
+        hash_algorithm: Algorithm::from_str(&manifest.hash_algorithm, false)
+            .expect("hash algorithm"),
+        message_version,
+        sighash_mode: Sighash::from_str(&manifest.sighash_mode, false).expect("sighash mode"),
+        always_success: args
+            .always_success
+            .clone()
+            .unwrap_or_else(|| manifest.always_success.path.clone()),
+        // The config is recorded in full in the manifest
+        config: None,
+    }
+}
+
+/// Compares a saved file with its regenerated content, describing the
+/// first differing byte if any
+fn compare_file(path: &str, regenerated: String) -> Option<String> {
+    let recorded = match fs::read(path) {
+        Ok(recorded) => recorded,
+        Err(e) => return Some(format!("cannot read {}: {}", path, e)),
+    };
+    let regenerated = regenerated.into_bytes();
+    if recorded == regenerated {
+        return None;
+    }
+    let offset = recorded
+        .iter()
+        .zip(&regenerated)
+        .position(|(a, b)| a != b)
+        .unwrap_or(recorded.len().min(regenerated.len()));
+    Some(format!(
+        "{} differs from byte {}, recorded {} bytes, regenerated {} bytes",
+        path,
+        offset,
+        recorded.len(),
+        regenerated.len()
+    ))
+}
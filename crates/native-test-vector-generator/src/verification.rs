@@ -0,0 +1,96 @@
+use crate::manifest::Verification;
+use ckb_mock_tx_types::MockTransaction;
+use ckb_testtool::{
+    ckb_types::{bytes::Bytes, core::TransactionView},
+    context::Context,
+};
+use test_utils::script_error_code;
+
+/// Cycle limit when verifying vectors, large enough for super large data
+pub const MAX_CYCLES: u64 = 1_000_000_000;
+
+/// Runs +tx+ once per contract, with the contract's binary swapped in as the
+/// code of the lock used by the input cell at +index+. The lock is located
+/// by type hash, so the tx itself, and hence the message, stays the same.
+/// Other script groups keep their own lock code. Nothing is replayed
+/// without contracts. Fails when the lock cannot be located, e.g., it is
+/// referenced by data hash.
+pub fn verify_vector<'a, I>(
+    context: &mut Context,
+    tx: &TransactionView,
+    index: usize,
+    contracts: I,
+) -> Result<Vec<Verification>, String>
+where
+    I: IntoIterator<Item = (&'a String, &'a Bytes)>,
+{
+    let contracts: Vec<_> = contracts.into_iter().collect();
+    if contracts.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let input = tx
+        .inputs()
+        .get(index)
+        .ok_or_else(|| format!("input {} is out of range", index))?;
+    let lock = context
+        .get_cell(&input.previous_output())
+        .ok_or_else(|| format!("input cell {} is missing", index))?
+        .0
+        .lock();
+    let code_out_point = context
+        .cells_by_type_hash
+        .get(&lock.code_hash())
+        .ok_or_else(|| {
+            format!(
+                "lock code of input cell {} is not found by type hash",
+                index
+            )
+        })?
+        .clone();
+    // Keeps debug output of contracts away from generator output
+    context.set_capture_debug(true);
+
+    Ok(contracts
+        .into_iter()
+        .map(|(path, binary)| {
+            context
+                .cells
+                .get_mut(&code_out_point)
+                .expect("lock code cell")
+                .1 = binary.clone();
+
+            let result = context.verify_tx(tx, MAX_CYCLES);
+            Verification {
+                contract: path.clone(),
+                passed: result.is_ok(),
+                error_code: result.as_ref().err().and_then(script_error_code),
+                cycles: result.ok(),
+            }
+        })
+        .collect())
+}
+
+/// Rebuilds a context containing all cells & headers referenced by
+/// +mock_tx+, so a saved vector can be replayed
+pub fn context_from_mock_tx(mock_tx: &MockTransaction) -> Context {
+    let mut context = Context::default();
+    for input in &mock_tx.mock_info.inputs {
+        context.create_cell_with_out_point(
+            input.input.previous_output(),
+            input.output.clone(),
+            input.data.clone(),
+        );
+    }
+    for cell_dep in &mock_tx.mock_info.cell_deps {
+        context.create_cell_with_out_point(
+            cell_dep.cell_dep.out_point(),
+            cell_dep.output.clone(),
+            cell_dep.data.clone(),
+        );
+    }
+    for header in &mock_tx.mock_info.header_deps {
+        context.insert_header(header.clone());
+    }
+    context
+}
@@ -0,0 +1,195 @@
+use crate::{
+    context_from_mock_tx, expected_outcome, verify_vector, Algorithm, Expected, Group, Manifest,
+    Sighash, VerifyArgs, MANIFEST_FILE,
+};
+use ckb_mock_tx_types::{MockTransaction, ReprMockTransaction};
+use ckb_testtool::ckb_types::bytes::Bytes;
+use clap::ValueEnum;
+use std::fs;
+use std::path::Path;
+use test_utils::MessageVersion;
+
+/// Verifies every vector in +args.dir+, printing a report per vector.
+/// Returns true when all vectors are valid.
+///
+/// The hash algorithm & sighash mode are read from the manifest, folders
+/// generated before the manifest was introduced use the defaults.
+pub fn verify_dir(args: &VerifyArgs) -> bool {
+    let dir = Path::new(&args.dir);
+    let (algorithm, sighash) = match fs::read_to_string(dir.join(MANIFEST_FILE)) {
+        Ok(content) => {
+            let manifest: Manifest = serde_json::from_str(&content).expect("parse manifest");
+            (
+                Algorithm::from_str(&manifest.hash_algorithm, false).expect("hash algorithm"),
+                Sighash::from_str(&manifest.sighash_mode, false).expect("sighash mode"),
+            )
+        }
+        Err(_) => (Algorithm::Blake2b, Sighash::All),
+    };
+    let contracts: Vec<(String, Bytes)> = args
+        .contracts
+        .iter()
+        .map(|path| (path.clone(), fs::read(path).expect("read").into()))
+        .collect();
+
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .expect("read dir")
+        .map(|entry| entry.expect("dir entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter(|path| path.file_name().is_some_and(|name| name != MANIFEST_FILE))
+        .map(|path| {
+            path.file_stem()
+                .expect("file stem")
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    names.sort();
+    if names.is_empty() {
+        eprintln!("No vectors found in {}", args.dir);
+        return false;
+    }
+
+    let mut failed = 0;
+    for name in &names {
+        let problems = verify_one(
+            &dir.join(name),
+            algorithm,
+            sighash,
+            contracts.iter().map(|(path, binary)| (path, binary)),
+        );
+        if problems.is_empty() {
+            println!("{}: ok", name);
+        } else {
+            failed += 1;
+            println!("{}: FAILED", name);
+            for problem in problems {
+                println!("  {}", problem);
+            }
+        }
+    }
+    println!("{} of {} vectors failed", failed, names.len());
+    failed == 0
+}
+
+/// Verifies a single vector, returning all problems found
+fn verify_one<'a, I>(
+    path: &Path,
+    algorithm: Algorithm,
+    sighash: Sighash,
+    contracts: I,
+) -> Vec<String>
+where
+    I: IntoIterator<Item = (&'a String, &'a Bytes)>,
+{
+    let path = path.to_str().expect("os str");
+    let mut problems = vec![];
+
+    let mock_tx: MockTransaction = match fs::read_to_string(format!("{}.json", path))
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            serde_json::from_str::<ReprMockTransaction>(&content).map_err(|e| e.to_string())
+        }) {
+        Ok(mock_tx) => mock_tx.into(),
+        Err(e) => return vec![format!("invalid tx file: {}", e)],
+    };
+    let indices: Vec<usize> = match fs::read_to_string(format!("{}.indices", path))
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+    {
+        Ok(indices) => indices,
+        Err(e) => return vec![format!("invalid indices file: {}", e)],
+    };
+    if indices.is_empty() {
+        return vec!["indices file contains no index".to_string()];
+    }
+    // Vectors generated before .version files were introduced follow the
+    // original specification
+    let version = match fs::read_to_string(format!("{}.version", path)) {
+        Ok(content) => match content.trim().parse::<u8>().map(MessageVersion::try_from) {
+            Ok(Ok(version)) => version,
+            _ => return vec![format!("invalid version file: {}", content)],
+        },
+        Err(_) => MessageVersion::V1,
+    };
+    let recorded = fs::read_to_string(format!("{}.hash", path))
+        .ok()
+        .map(|hash| hash.trim().to_string());
+
+    let expected = |indices: &[usize]| {
+        expected_outcome(&mock_tx, indices, algorithm.into(), version, sighash.into())
+    };
+
+    match (expected(&indices), &recorded) {
+        (Expected::Hash { hash }, Some(recorded)) if &hash != recorded => problems.push(format!(
+            "hash mismatch, recorded {}, generated {}",
+            recorded, hash
+        )),
+        (Expected::Hash { hash }, None) => problems.push(format!(
+            "no hash file, but generation succeeds with {}",
+            hash
+        )),
+        (Expected::Failure { reason }, Some(_)) => problems.push(format!(
+            "hash file exists, but generation fails: {}",
+            reason
+        )),
+        _ => (),
+    }
+
+    // Multi-group vectors also record every script group in .groups files
+    let groups: Vec<Group> = match fs::read_to_string(format!("{}.groups", path)) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(groups) => groups,
+            Err(e) => return vec![format!("invalid groups file: {}", e)],
+        },
+        Err(_) => vec![],
+    };
+    if groups.first().is_some_and(|group| group.indices != indices) {
+        problems.push("first group differs from the indices file".to_string());
+    }
+    for (i, group) in groups.iter().enumerate() {
+        if group.indices.is_empty() {
+            problems.push(format!("group {} contains no index", i));
+            continue;
+        }
+        let generated = expected(&group.indices);
+        if generated != group.expected {
+            problems.push(format!(
+                "group {} mismatch, recorded {:?}, generated {:?}",
+                i, group.expected, generated
+            ));
+        }
+    }
+
+    // A vector with hashes of all groups shall pass all contracts, while
+    // other vectors shall fail all of them
+    let passing = recorded.is_some()
+        && groups
+            .iter()
+            .all(|group| matches!(group.expected, Expected::Hash { .. }));
+    let mut context = context_from_mock_tx(&mock_tx);
+    let tx = mock_tx.core_transaction();
+    let verifications = match verify_vector(&mut context, &tx, indices[0], contracts) {
+        Ok(verifications) => verifications,
+        Err(e) => {
+            problems.push(format!("cannot replay: {}", e));
+            return problems;
+        }
+    };
+    for verification in verifications {
+        if verification.passed != passing {
+            problems.push(format!(
+                "contract {} {}, error code: {:?}",
+                verification.contract,
+                if verification.passed {
+                    "passes"
+                } else {
+                    "fails"
+                },
+                verification.error_code,
+            ));
+        }
+    }
+
+    problems
+}
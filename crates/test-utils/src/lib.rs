@@ -1,43 +1,329 @@
 use ckb_testtool::{
-    ckb_hash::{new_blake2b, Blake2b},
+    ckb_chain_spec::consensus::TYPE_ID_CODE_HASH,
+    ckb_error::Error,
+    ckb_script::{ScriptError, TransactionScriptError},
     ckb_types::{
         bytes::Bytes,
-        core::{TransactionBuilder, TransactionView},
+        core::{Capacity, DepType, ScriptHashType, TransactionBuilder, TransactionView},
         packed::*,
         prelude::*,
     },
-    context::Context,
+    context::{Context, Message},
 };
 use ckb_tx_message_all_utils::ckb_tx_message_all_from_mock_tx::{
-    generate_ckb_tx_message_all_from_mock_tx, ScriptOrIndex,
+    generate_ckb_tx_message_all_from_mock_tx,
+    generate_ckb_tx_message_all_from_mock_tx_with_version, generate_ckb_tx_message_from_mock_tx,
+    ScriptOrIndex,
 };
-use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
-use std::io;
+use ckb_tx_message_all_utils::ckb_tx_message_all_in_ckb_vm::{
+    DUMPED_PREIMAGE_PREFIX, TRACE_SEGMENT_PREFIX,
+};
+pub use ckb_tx_message_all_utils::exit_code::ExitCode;
+pub use ckb_tx_message_all_utils::fixture::{Fixture, FixtureError};
+pub use ckb_tx_message_all_utils::hasher::{HashAlgorithm, Hasher};
+pub use ckb_tx_message_all_utils::message_version::MessageVersion;
+pub use ckb_tx_message_all_utils::secp256k1_blake160::ERROR_PUBKEY_HASH_MISMATCH;
+pub use ckb_tx_message_all_utils::sighash_mode::SighashMode;
+pub use k256::ecdsa::SigningKey;
+pub use rng::StableRng;
+use serde::{Deserialize, Serialize};
+pub use shape::TxShape;
+use std::ops::RangeInclusive;
+
+mod rng;
+mod shape;
+
+/// Produces content of the lock field in the first witness of the
+/// CKB_TX_MESSAGE_ALL validating script group, from the preimage
+pub type Signer = Box<dyn Fn(&[u8]) -> Bytes>;
+
+/// Describes the CKB_TX_MESSAGE_ALL validating lock used in built transactions
+pub struct LockSetup {
+    pub contract_bin: Bytes,
+    pub args: Bytes,
+    /// Binaries deployed as cell deps, e.g., scripts to spawn or exec
+    pub dep_bins: Vec<Bytes>,
+    pub signer: Signer,
+    /// Sighash mode of the preimage passed to signer
+    pub sighash_mode: SighashMode,
+}
 
-pub struct Hasher(Blake2b);
+impl LockSetup {
+    /// A lock with empty args, which expects the ckb flavored blake2b hash
+    /// of the preimage in the lock field
+    pub fn new(contract_bin: Bytes) -> Self {
+        LockSetup {
+            contract_bin,
+            args: Bytes::new(),
+            dep_bins: vec![],
+            signer: Box::new(hash_signer(HashAlgorithm::CkbBlake2b)),
+            sighash_mode: SighashMode::All,
+        }
+    }
+
+    pub fn args(mut self, args: Bytes) -> Self {
+        self.args = args;
+        self
+    }
 
-impl Hasher {
-    pub fn hash(self) -> [u8; 32] {
-        let mut result = [0u8; 32];
-        self.0.finalize(&mut result);
+    pub fn dep_bin(mut self, dep_bin: Bytes) -> Self {
+        self.dep_bins.push(dep_bin);
+        self
+    }
+
+    pub fn signer<F: Fn(&[u8]) -> Bytes + 'static>(mut self, signer: F) -> Self {
+        self.signer = Box::new(signer);
+        self
+    }
+
+    pub fn sighash_mode(mut self, sighash_mode: SighashMode) -> Self {
+        self.sighash_mode = sighash_mode;
+        self
+    }
+}
+
+/// A signer that simply puts the preimage hash in the lock field
+pub fn hash_signer(algorithm: HashAlgorithm) -> impl Fn(&[u8]) -> Bytes {
+    move |preimage| {
+        let mut hasher = Hasher::new(algorithm);
+        hasher.update(preimage);
+        hasher.hash().to_vec().into()
+    }
+}
+
+/// Deterministically derive a secp256k1 private key from +seed+
+pub fn secp256k1_key(seed: u64) -> SigningKey {
+    let mut hasher = Hasher::default();
+    hasher.update(&seed.to_le_bytes());
+    SigningKey::from_slice(&hasher.hash()).expect("valid secp256k1 private key")
+}
+
+/// First 20 bytes of the ckb flavored blake2b hash of the compressed public key
+pub fn secp256k1_blake160(key: &SigningKey) -> [u8; 20] {
+    let mut hasher = Hasher::default();
+    hasher.update(key.verifying_key().to_encoded_point(true).as_bytes());
+    hasher.hash()[0..20].try_into().unwrap()
+}
+
+/// A 65-byte secp256k1 recoverable signature(r, s, then recovery id) of the
+/// preimage's ckb flavored blake2b hash. RFC6979 nonces are used, so the same
+/// key & preimage always yield the same signature.
+pub fn secp256k1_sign(key: &SigningKey, preimage: &[u8]) -> [u8; 65] {
+    let mut hasher = Hasher::default();
+    hasher.update(preimage);
+    let (signature, recovery_id) = key
+        .sign_prehash_recoverable(&hasher.hash())
+        .expect("secp256k1 sign");
+    let mut result = [0u8; 65];
+    result[0..64].copy_from_slice(&signature.to_bytes());
+    result[64] = recovery_id.to_byte();
+    result
+}
+
+/// A signer that puts a secp256k1 recoverable signature in the lock field
+pub fn secp256k1_signer(key: SigningKey) -> impl Fn(&[u8]) -> Bytes {
+    move |preimage| secp256k1_sign(&key, preimage).to_vec().into()
+}
+
+/// M-of-N multisig configuration, serialized in the same layout as the
+/// system multisig script: S(reserved, always 0) | R | M | N | N public
+/// key hashes
+#[derive(Clone, Debug)]
+pub struct MultisigConfig {
+    /// R: signatures from the first R public keys are always required
+    pub require_first_n: u8,
+    /// M: number of signatures required
+    pub threshold: u8,
+    pub pubkey_hashes: Vec<[u8; 20]>,
+}
+
+impl MultisigConfig {
+    pub fn new(require_first_n: u8, threshold: u8, keys: &[SigningKey]) -> Self {
+        MultisigConfig {
+            require_first_n,
+            threshold,
+            pubkey_hashes: keys.iter().map(secp256k1_blake160).collect(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = vec![
+            0,
+            self.require_first_n,
+            self.threshold,
+            self.pubkey_hashes.len().try_into().expect("too many keys"),
+        ];
+        for pubkey_hash in &self.pubkey_hashes {
+            result.extend_from_slice(pubkey_hash);
+        }
         result
     }
+
+    /// First 20 bytes of the ckb flavored blake2b hash of the serialized
+    /// config, used as script args of the multisig lock
+    pub fn blake160(&self) -> [u8; 20] {
+        let mut hasher = Hasher::default();
+        hasher.update(&self.to_bytes());
+        hasher.hash()[0..20].try_into().unwrap()
+    }
+
+    /// Combines partial signatures collected from signers into the lock
+    /// field: the serialized config followed by the signatures as they are
+    /// ordered in +signatures+.
+    pub fn lock(&self, signatures: &[[u8; 65]]) -> Bytes {
+        let mut result = self.to_bytes();
+        for signature in signatures {
+            result.extend_from_slice(signature);
+        }
+        result.into()
+    }
 }
 
-impl Default for Hasher {
-    fn default() -> Self {
-        Hasher(new_blake2b())
+/// A signer that collects a partial signature from each of +keys+ in the
+/// order given, then combines them with +config+ into the lock field
+pub fn multisig_signer(config: MultisigConfig, keys: Vec<SigningKey>) -> impl Fn(&[u8]) -> Bytes {
+    move |preimage| {
+        let signatures: Vec<[u8; 65]> = keys
+            .iter()
+            .map(|key| secp256k1_sign(key, preimage))
+            .collect();
+        config.lock(&signatures)
     }
 }
 
-impl io::Write for Hasher {
-    fn write(&mut self, data: &[u8]) -> Result<usize, io::Error> {
-        self.0.update(data);
-        Ok(data.len())
+/// Generate CKB_TX_MESSAGE_ALL preimage off-chain, using input cells denoted
+/// by index as current script group
+pub fn generate_preimage(context: &Context, tx: &TransactionView, index: usize) -> Vec<u8> {
+    generate_preimage_with_version(context, tx, index, MessageVersion::V1)
+}
+
+/// Generate CKB_TX_MESSAGE_ALL preimage off-chain following the specified
+/// revision of the spec, using input cells denoted by index as current
+/// script group
+pub fn generate_preimage_with_version(
+    context: &Context,
+    tx: &TransactionView,
+    index: usize,
+    version: MessageVersion,
+) -> Vec<u8> {
+    let mock_tx = context.dump_tx(tx).expect("dump tx");
+    let mut preimage = vec![];
+    generate_ckb_tx_message_all_from_mock_tx_with_version(
+        &mock_tx.into(),
+        ScriptOrIndex::Index(index),
+        version,
+        &mut preimage,
+    )
+    .expect("generate ckb tx message all");
+    preimage
+}
+
+/// Generate preimage of the specified sighash mode off-chain, using input
+/// cells denoted by index as current script group
+pub fn generate_preimage_with_sighash_mode(
+    context: &Context,
+    tx: &TransactionView,
+    index: usize,
+    mode: SighashMode,
+) -> Vec<u8> {
+    let mock_tx = context.dump_tx(tx).expect("dump tx");
+    let mut preimage = vec![];
+    generate_ckb_tx_message_from_mock_tx(
+        &mock_tx.into(),
+        ScriptOrIndex::Index(index),
+        mode,
+        &mut preimage,
+    )
+    .expect("generate ckb tx message");
+    preimage
+}
+
+/// Generate a self-contained fixture of the tx, using input cells denoted by
+/// indices as current script group
+pub fn generate_fixture(
+    context: &Context,
+    tx: &TransactionView,
+    indices: &[usize],
+    version: MessageVersion,
+    mode: SighashMode,
+) -> Fixture {
+    let mock_tx = context.dump_tx(tx).expect("dump tx");
+    Fixture::generate_from_mock_tx(&mock_tx.into(), indices, version, mode)
+        .expect("generate fixture")
+}
+
+/// Generate CKB_TX_MESSAGE_ALL preimage off-chain, using cells with the
+/// specified type script as current script group
+pub fn generate_type_script_preimage(
+    context: &Context,
+    tx: &TransactionView,
+    type_script: &Script,
+) -> Vec<u8> {
+    let mock_tx = context.dump_tx(tx).expect("dump tx");
+    let mut preimage = vec![];
+    generate_ckb_tx_message_all_from_mock_tx(
+        &mock_tx.into(),
+        ScriptOrIndex::TypeScript(type_script.clone()),
+        &mut preimage,
+    )
+    .expect("generate ckb tx message all");
+    preimage
+}
+
+/// Collect preimage dumped by dump-ckb-tx-message-all-preimage contract
+/// from captured debug messages. Fails when a chunk is not valid hex.
+pub fn extract_dumped_preimage(messages: &[Message]) -> Result<Vec<u8>, String> {
+    let mut preimage = vec![];
+    for chunk in messages
+        .iter()
+        .filter_map(|m| m.message.strip_prefix(DUMPED_PREIMAGE_PREFIX))
+    {
+        let data =
+            hex::decode(chunk).map_err(|e| format!("invalid preimage chunk {}: {}", chunk, e))?;
+        preimage.extend(data);
     }
+    Ok(preimage)
+}
+
+/// Boundary of a preimage segment emitted by the trace feature
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TracedSegment {
+    pub label: String,
+    pub offset: usize,
+    pub length: usize,
+}
+
+/// Collect segment boundaries emitted by contracts built with the trace
+/// feature of ckb-tx-message-all-utils from captured debug messages
+pub fn extract_traced_segments(messages: &[Message]) -> Result<Vec<TracedSegment>, String> {
+    messages
+        .iter()
+        .filter_map(|m| m.message.strip_prefix(TRACE_SEGMENT_PREFIX))
+        .map(|segment| {
+            let invalid = || format!("invalid segment: {}", segment);
+            let (label, boundary) = segment.rsplit_once(": ").ok_or_else(invalid)?;
+            let (offset, length) = boundary
+                .strip_prefix("offset ")
+                .and_then(|b| b.split_once(", length "))
+                .ok_or_else(invalid)?;
+            Ok(TracedSegment {
+                label: label.to_string(),
+                offset: offset.parse().map_err(|_| invalid())?,
+                length: length.parse().map_err(|_| invalid())?,
+            })
+        })
+        .collect()
+}
 
-    fn flush(&mut self) -> Result<(), io::Error> {
-        Ok(())
+/// Exit code of the script failing verification with +error+, None when
+/// verification fails for other reasons
+pub fn script_error_code(error: &Error) -> Option<i8> {
+    match error
+        .downcast_ref::<TransactionScriptError>()?
+        .script_error()
+    {
+        ScriptError::ValidationFailure(_, exit_code) => Some(*exit_code),
+        _ => None,
     }
 }
 
@@ -48,9 +334,29 @@ pub fn build_bare_tx(
     always_success_bin: Bytes,
     seed: u64,
 ) -> (Context, TransactionView, Vec<usize>) {
-    let mut rng = StdRng::seed_from_u64(seed);
+    build_bare_tx_with_lock(LockSetup::new(contract_bin), always_success_bin, seed)
+}
+
+/// Same as build_bare_tx, using provided lock setup
+pub fn build_bare_tx_with_lock(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    build_bare_tx_with_shape(lock, always_success_bin, &TxShape::bare(), seed)
+}
 
-    _build_bare_tx(contract_bin, always_success_bin, &mut rng, 1, 5)
+/// Same as build_bare_tx, using provided lock setup & transaction shape.
+/// Witness related fields of the shape are not used.
+pub fn build_bare_tx_with_shape(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    shape: &TxShape,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    let mut rng = StableRng::seed_from_u64(seed);
+
+    _build_bare_tx(&lock, always_success_bin, &mut rng, shape)
 }
 
 /// Build a bare minimal transaction with 3 - 5 input cells
@@ -60,9 +366,20 @@ pub fn build_bare_tx_multiple_input_cells(
     always_success_bin: Bytes,
     seed: u64,
 ) -> (Context, TransactionView, Vec<usize>) {
-    let mut rng = StdRng::seed_from_u64(seed);
+    build_bare_tx_multiple_input_cells_with_lock(
+        LockSetup::new(contract_bin),
+        always_success_bin,
+        seed,
+    )
+}
 
-    _build_bare_tx(contract_bin, always_success_bin, &mut rng, 3, 5)
+/// Same as build_bare_tx_multiple_input_cells, using provided lock setup
+pub fn build_bare_tx_multiple_input_cells_with_lock(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    build_bare_tx_with_shape(lock, always_success_bin, &TxShape::multiple_input(), seed)
 }
 
 /// Build a proper transaction with 3 - 5 input cells
@@ -72,19 +389,32 @@ pub fn build_tx_with_witness_data(
     always_success_bin: Bytes,
     seed: u64,
 ) -> (Context, TransactionView, Vec<usize>) {
-    let mut rng = StdRng::seed_from_u64(seed);
+    build_tx_with_witness_data_with_lock(LockSetup::new(contract_bin), always_success_bin, seed)
+}
 
-    let (mut context, uncompleted_tx, indices) = _build_bare_uncompleted_tx_with_witness(
-        contract_bin,
-        always_success_bin,
-        &mut rng,
-        3,
-        5,
-        10,
-        200,
-    );
+/// Same as build_tx_with_witness_data, using provided lock setup
+pub fn build_tx_with_witness_data_with_lock(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    build_tx_with_witness_data_with_shape(lock, always_success_bin, &TxShape::witness(), seed)
+}
+
+/// Same as build_tx_with_witness_data, using provided lock setup &
+/// transaction shape
+pub fn build_tx_with_witness_data_with_shape(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    shape: &TxShape,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    let mut rng = StableRng::seed_from_u64(seed);
+
+    let (mut context, uncompleted_tx, indices) =
+        _build_bare_uncompleted_tx_with_witness(&lock, always_success_bin, &mut rng, shape);
 
-    let signed_tx = complete_and_sign_tx(&mut context, uncompleted_tx, indices[0]);
+    let signed_tx = complete_and_sign_tx(&mut context, uncompleted_tx, indices[0], &lock);
 
     (context, signed_tx, indices)
 }
@@ -96,24 +426,57 @@ pub fn build_tx_with_super_large_data(
     always_success_bin: Bytes,
     seed: u64,
 ) -> (Context, TransactionView, Vec<usize>) {
-    let mut rng = StdRng::seed_from_u64(seed);
+    build_tx_with_super_large_data_with_lock(LockSetup::new(contract_bin), always_success_bin, seed)
+}
 
-    let (mut context, uncompleted_tx, indices) = _build_bare_uncompleted_tx_with_witness(
-        contract_bin,
+/// Same as build_tx_with_super_large_data, except that provided args
+/// will be used in the lock script
+pub fn build_tx_with_super_large_data_and_args(
+    contract_bin: Bytes,
+    always_success_bin: Bytes,
+    args: Bytes,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    build_tx_with_super_large_data_with_lock(
+        LockSetup::new(contract_bin).args(args),
         always_success_bin,
-        &mut rng,
-        2,
-        4,
-        70000,
-        300000,
-    );
+        seed,
+    )
+}
+
+/// Same as build_tx_with_super_large_data, using provided lock setup
+pub fn build_tx_with_super_large_data_with_lock(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    build_tx_with_super_large_data_with_shape(
+        lock,
+        always_success_bin,
+        &TxShape::large_data(),
+        seed,
+    )
+}
+
+/// Same as build_tx_with_super_large_data, using provided lock setup &
+/// transaction shape
+pub fn build_tx_with_super_large_data_with_shape(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    shape: &TxShape,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    let mut rng = StableRng::seed_from_u64(seed);
+
+    let (mut context, uncompleted_tx, indices) =
+        _build_bare_uncompleted_tx_with_witness(&lock, always_success_bin, &mut rng, shape);
 
     // Modify some input cells with large data
     {
         let modified_count = rng.gen_range(1..=uncompleted_tx.inputs().len());
 
         for cell_input in uncompleted_tx.inputs().into_iter().take(modified_count) {
-            let data_length = rng.gen_range(70000..=300000);
+            let data_length = rng.gen_range(shape.large_input_data.clone());
             let data = random_data(&mut rng, data_length);
 
             {
@@ -126,60 +489,323 @@ pub fn build_tx_with_super_large_data(
         }
     }
 
-    let signed_tx = complete_and_sign_tx(&mut context, uncompleted_tx, indices[0]);
+    let signed_tx = complete_and_sign_tx(&mut context, uncompleted_tx, indices[0], &lock);
 
     (context, signed_tx, indices)
 }
 
-fn _build_bare_tx<R: Rng>(
-    contract_bin: Bytes,
+/// Build a proper transaction with one CKB_TX_MESSAGE_ALL validating script
+/// group of 3 - 5 input cells per lock, witnesses shall also be filled with
+/// real data. Each group is signed independently, so locks shall differ in
+/// contract or args. Returned are indices of input cells of each group, in
+/// the order of locks.
+pub fn build_multi_group_tx(
+    locks: Vec<LockSetup>,
     always_success_bin: Bytes,
-    rng: &mut R,
-    min_current_group_input_cells: usize,
-    max_current_group_input_cells: usize,
+    seed: u64,
+) -> (Context, TransactionView, Vec<Vec<usize>>) {
+    build_multi_group_tx_with_shape(locks, always_success_bin, &TxShape::witness(), seed)
+}
+
+/// Same as build_multi_group_tx, using provided transaction shape. Group
+/// related fields of the shape apply to each group.
+pub fn build_multi_group_tx_with_shape(
+    locks: Vec<LockSetup>,
+    always_success_bin: Bytes,
+    shape: &TxShape,
+    seed: u64,
+) -> (Context, TransactionView, Vec<Vec<usize>>) {
+    let mut rng = StableRng::seed_from_u64(seed);
+    let locks: Vec<&LockSetup> = locks.iter().collect();
+
+    let (mut context, uncompleted_tx, groups) =
+        _build_multi_group_uncompleted_tx_with_witness(&locks, always_success_bin, &mut rng, shape);
+
+    let signed_tx = complete_and_sign_groups(
+        &mut context,
+        uncompleted_tx,
+        &locks,
+        &groups,
+        GroupScript::Lock,
+    );
+
+    (context, signed_tx, groups)
+}
+
+/// Layouts of witnesses not covering all input cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SparseWitnesses {
+    /// Witnesses stop right after the first witness of current script group,
+    /// so the remaining input cells of the group have no witness
+    MissingLaterGroupWitnesses,
+    /// Witnesses stop at a random index after the first witness of current
+    /// script group, but no later than the last input cell of the group, so
+    /// at least the last group input cell has no witness
+    FewerWitnessesThanInputs,
+    /// Current script group has a single input cell, which is the last input
+    /// cell, followed by extra witnesses
+    FirstGroupInputLast,
+    /// Witnesses stop before the first input cell of current script group,
+    /// no CKB_TX_MESSAGE_ALL can be generated, hence the tx is not signed
+    MissingFirstGroupWitness,
+}
+
+/// Build a transaction with 3 - 5 input cells using provided lock, whose
+/// witnesses follow +layout+
+pub fn build_tx_with_sparse_witnesses(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    layout: SparseWitnesses,
+    seed: u64,
 ) -> (Context, TransactionView, Vec<usize>) {
-    let (mut context, uncompleted_tx, indices) = _build_bare_uncompleted_tx(
-        contract_bin,
+    build_tx_with_sparse_witnesses_with_shape(
+        lock,
         always_success_bin,
-        rng,
-        min_current_group_input_cells,
-        max_current_group_input_cells,
+        layout,
+        &TxShape::witness(),
+        seed,
+    )
+}
+
+/// Same as build_tx_with_sparse_witnesses, using provided transaction shape.
+/// group_inputs of the shape is raised to at least 2 when the layout leaves
+/// later input cells of the group without witness, and is set to 1 for
+/// FirstGroupInputLast.
+pub fn build_tx_with_sparse_witnesses_with_shape(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    layout: SparseWitnesses,
+    shape: &TxShape,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    let mut rng = StableRng::seed_from_u64(seed);
+    let group_inputs = match layout {
+        SparseWitnesses::FirstGroupInputLast => 1..=1,
+        SparseWitnesses::MissingLaterGroupWitnesses | SparseWitnesses::FewerWitnessesThanInputs => {
+            (*shape.group_inputs.start()).max(2)..=(*shape.group_inputs.end()).max(2)
+        }
+        SparseWitnesses::MissingFirstGroupWitness => shape.group_inputs.clone(),
+    };
+    let shape = TxShape {
+        group_inputs,
+        ..shape.clone()
+    };
+
+    let (mut context, uncompleted_tx, mut indices) =
+        _build_bare_uncompleted_tx_with_witness(&lock, always_success_bin, &mut rng, &shape);
+
+    let mut inputs: Vec<_> = uncompleted_tx.inputs().into_iter().collect();
+    let mut witnesses: Vec<_> = uncompleted_tx.witnesses().into_iter().collect();
+    match layout {
+        SparseWitnesses::MissingLaterGroupWitnesses => witnesses.truncate(indices[0] + 1),
+        SparseWitnesses::FewerWitnessesThanInputs => {
+            witnesses.truncate(rng.gen_range(indices[0] + 1..=*indices.last().unwrap()))
+        }
+        SparseWitnesses::FirstGroupInputLast => {
+            // Swap the group's input cell with the last input cell, along
+            // with their witnesses
+            let last = inputs.len() - 1;
+            inputs.swap(indices[0], last);
+            witnesses.swap(indices[0], last);
+            indices = vec![last];
+        }
+        SparseWitnesses::MissingFirstGroupWitness => {
+            witnesses.truncate(rng.gen_range(0..=indices[0]))
+        }
+    }
+    let uncompleted_tx = uncompleted_tx
+        .as_advanced_builder()
+        .set_inputs(inputs)
+        .set_witnesses(witnesses)
+        .build();
+
+    let tx = match layout {
+        SparseWitnesses::MissingFirstGroupWitness => context.complete_tx(uncompleted_tx),
+        _ => complete_and_sign_tx(&mut context, uncompleted_tx, indices[0], &lock),
+    };
+
+    (context, tx, indices)
+}
+
+/// Content of an optional field in WitnessArgs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldContent {
+    /// The field is None
+    None,
+    /// The field is Some, containing empty bytes
+    Empty,
+    /// The field is Some, containing random bytes, or the content produced
+    /// by the signer for lock
+    Data,
+}
+
+impl FieldContent {
+    pub const ALL: [FieldContent; 3] =
+        [FieldContent::None, FieldContent::Empty, FieldContent::Data];
+
+    fn apply(self, data: Bytes) -> BytesOpt {
+        match self {
+            FieldContent::None => None,
+            FieldContent::Empty => Some(Bytes::new()),
+            FieldContent::Data => Some(data),
+        }
+        .pack()
+    }
+}
+
+/// Fields of the first witness of current script group
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessArgsFields {
+    pub lock: FieldContent,
+    pub input_type: FieldContent,
+    pub output_type: FieldContent,
+}
+
+/// Build a proper transaction with 3 - 5 input cells using provided lock,
+/// whose first witness of current script group has fields of +fields+. A
+/// seed leads to the same tx regardless of +fields+, except for the first
+/// witness of the group, so messages of different fields can be compared.
+/// The lock field is the signer's output only for FieldContent::Data, the
+/// tx is left unsigned otherwise.
+pub fn build_tx_with_witness_args_fields(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    fields: WitnessArgsFields,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    build_tx_with_witness_args_fields_with_shape(
+        lock,
+        always_success_bin,
+        fields,
+        &TxShape::witness(),
+        seed,
+    )
+}
+
+/// Same as build_tx_with_witness_args_fields, using provided transaction
+/// shape. Witness lengths of the shape shall start from at least 1, so
+/// FieldContent::Data never ends up the same as FieldContent::Empty.
+pub fn build_tx_with_witness_args_fields_with_shape(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    fields: WitnessArgsFields,
+    shape: &TxShape,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    assert!(
+        *shape.witness_length.start() >= 1,
+        "witness_length shall start from at least 1"
     );
+    let mut rng = StableRng::seed_from_u64(seed);
 
-    let signed_tx = complete_and_sign_tx(&mut context, uncompleted_tx, indices[0]);
+    let (mut context, uncompleted_tx, indices) =
+        _build_bare_uncompleted_tx_with_witness(&lock, always_success_bin, &mut rng, shape);
 
-    (context, signed_tx, indices)
+    // Random data of input_type & output_type has already been filled in
+    let uncompleted_tx = map_first_witness(uncompleted_tx, indices[0], |witness_args| {
+        let input_type = witness_args.input_type().to_opt().unwrap().raw_data();
+        let output_type = witness_args.output_type().to_opt().unwrap().raw_data();
+        witness_args
+            .as_builder()
+            .input_type(fields.input_type.apply(input_type))
+            .output_type(fields.output_type.apply(output_type))
+            .build()
+    });
+    let signed_tx = complete_and_sign_tx(&mut context, uncompleted_tx, indices[0], &lock);
+    // The lock field is not part of the message
+    let tx = map_first_witness(signed_tx, indices[0], |witness_args| {
+        let lock = witness_args.lock().to_opt().unwrap().raw_data();
+        witness_args
+            .as_builder()
+            .lock(fields.lock.apply(lock))
+            .build()
+    });
+
+    (context, tx, indices)
 }
 
-fn _build_bare_uncompleted_tx_with_witness<R: Rng>(
+/// Build a bare minimal transaction with 1 - 5 input cells using the provided
+/// contract as type script and always success as lock script, mixed with
+/// cells of no type script. Output cells use the contract as type script as
+/// well. The expected hash is kept in the lock field of the first witness of
+/// the type script group. Returned indices are those of the group's input
+/// cells.
+pub fn build_tx_with_type_script(
     contract_bin: Bytes,
     always_success_bin: Bytes,
-    rng: &mut R,
-    min_current_group_input_cells: usize,
-    max_current_group_input_cells: usize,
-    min_witness_length: usize,
-    max_witness_length: usize,
+    seed: u64,
+) -> (Context, TransactionView, Vec<usize>) {
+    build_tx_with_type_script_with_lock(LockSetup::new(contract_bin), always_success_bin, seed)
+}
+
+/// Same as build_tx_with_type_script, using provided lock setup to describe
+/// the type script
+pub fn build_tx_with_type_script_with_lock(
+    lock: LockSetup,
+    always_success_bin: Bytes,
+    seed: u64,
 ) -> (Context, TransactionView, Vec<usize>) {
-    let (context, uncompleted_tx, indices) = _build_bare_uncompleted_tx(
-        contract_bin,
+    let mut rng = StableRng::seed_from_u64(seed);
+
+    _build_bare_uncompleted_tx(
+        &lock,
         always_success_bin,
-        rng,
-        min_current_group_input_cells,
-        max_current_group_input_cells,
-    );
+        &mut rng,
+        &TxShape::bare(),
+        GroupScript::Type,
+    )
+}
+
+fn _build_bare_tx(
+    lock: &LockSetup,
+    always_success_bin: Bytes,
+    rng: &mut StableRng,
+    shape: &TxShape,
+) -> (Context, TransactionView, Vec<usize>) {
+    let (mut context, uncompleted_tx, indices) =
+        _build_bare_uncompleted_tx(lock, always_success_bin, rng, shape, GroupScript::Lock);
+
+    let signed_tx = complete_and_sign_tx(&mut context, uncompleted_tx, indices[0], lock);
+
+    (context, signed_tx, indices)
+}
+
+fn _build_bare_uncompleted_tx_with_witness(
+    lock: &LockSetup,
+    always_success_bin: Bytes,
+    rng: &mut StableRng,
+    shape: &TxShape,
+) -> (Context, TransactionView, Vec<usize>) {
+    let (context, uncompleted_tx, mut groups) =
+        _build_multi_group_uncompleted_tx_with_witness(&[lock], always_success_bin, rng, shape);
+
+    (context, uncompleted_tx, groups.remove(0))
+}
+
+fn _build_multi_group_uncompleted_tx_with_witness(
+    locks: &[&LockSetup],
+    always_success_bin: Bytes,
+    rng: &mut StableRng,
+    shape: &TxShape,
+) -> (Context, TransactionView, Vec<Vec<usize>>) {
+    let (context, uncompleted_tx, groups) =
+        _build_multi_group_uncompleted_tx(locks, always_success_bin, rng, shape, GroupScript::Lock);
+    let first_witness_indices: Vec<_> = groups.iter().map(|indices| indices[0]).collect();
 
     // Modify the tx to fill in witness data
     let modified_tx = {
-        let generated_witness_count = uncompleted_tx.inputs().len() + rng.gen_range(1..=3);
+        let generated_witness_count =
+            uncompleted_tx.inputs().len() + rng.gen_range(shape.extra_witnesses.clone());
         let witnesses: Vec<_> = (0..generated_witness_count)
             .map(|i| {
-                if i == indices[0] {
+                if first_witness_indices.contains(&i) {
                     let current_data = uncompleted_tx.witnesses().get(i).unwrap().raw_data();
                     let current_witness_args = WitnessArgs::from_slice(&current_data).unwrap();
 
-                    let input_type_length = rng.gen_range(min_witness_length..=max_witness_length);
+                    let input_type_length = rng.gen_range(shape.witness_length.clone());
                     let input_type = random_data(rng, input_type_length);
-                    let output_type_length = rng.gen_range(min_witness_length..=max_witness_length);
+                    let output_type_length = rng.gen_range(shape.witness_length.clone());
                     let output_type = random_data(rng, output_type_length);
 
                     current_witness_args
@@ -189,7 +815,7 @@ fn _build_bare_uncompleted_tx_with_witness<R: Rng>(
                         .build()
                         .as_bytes()
                 } else {
-                    let length = rng.gen_range(min_witness_length..=max_witness_length);
+                    let length = rng.gen_range(shape.witness_length.clone());
                     random_data(rng, length)
                 }
             })
@@ -202,139 +828,326 @@ fn _build_bare_uncompleted_tx_with_witness<R: Rng>(
             .build()
     };
 
-    (context, modified_tx, indices)
+    (context, modified_tx, groups)
 }
 
-fn _build_bare_uncompleted_tx<R: Rng>(
-    contract_bin: Bytes,
+fn _build_bare_uncompleted_tx(
+    lock: &LockSetup,
     always_success_bin: Bytes,
-    rng: &mut R,
-    min_current_group_input_cells: usize,
-    max_current_group_input_cells: usize,
+    rng: &mut StableRng,
+    shape: &TxShape,
+    group_script: GroupScript,
 ) -> (Context, TransactionView, Vec<usize>) {
-    assert!(min_current_group_input_cells > 0);
+    let (context, uncompleted_tx, mut groups) =
+        _build_multi_group_uncompleted_tx(&[lock], always_success_bin, rng, shape, group_script);
+
+    (context, uncompleted_tx, groups.remove(0))
+}
+
+/// How contracts of lock setups are attached to cells of their script groups
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GroupScript {
+    Lock,
+    /// Cells of the group use the contract as type script, and always success
+    /// as lock script
+    Type,
+}
+
+// Builds a tx with one script group per lock, in the order of locks. Output
+// cells use the first lock.
+fn _build_multi_group_uncompleted_tx(
+    locks: &[&LockSetup],
+    always_success_bin: Bytes,
+    rng: &mut StableRng,
+    shape: &TxShape,
+    group_script: GroupScript,
+) -> (Context, TransactionView, Vec<Vec<usize>>) {
+    assert!(*shape.group_inputs.start() > 0);
 
     let mut context = Context::new_with_deterministic_rng();
-    let out_point = context.deploy_cell(contract_bin);
-    let always_success_out_point = context.deploy_cell(always_success_bin);
+    let always_success_out_point = deploy_cell(&mut context, always_success_bin);
+    let mut cell_deps: Vec<CellDep> = vec![];
+    for dep_bin in locks.iter().flat_map(|lock| &lock.dep_bins) {
+        let cell_dep = CellDep::new_builder()
+            .out_point(deploy_cell(&mut context, dep_bin.clone()))
+            .dep_type(DepType::Code.into())
+            .build();
+        if !cell_deps.contains(&cell_dep) {
+            cell_deps.push(cell_dep);
+        }
+    }
 
     // prepare scripts
-    let lock_script = context
-        .build_script(&out_point, Bytes::new())
-        .expect("script");
+    let lock_scripts: Vec<_> = locks
+        .iter()
+        .map(|lock| {
+            let out_point = deploy_cell(&mut context, lock.contract_bin.clone());
+            context
+                .build_script(&out_point, lock.args.clone())
+                .expect("script")
+        })
+        .collect();
+    for (i, lock_script) in lock_scripts.iter().enumerate() {
+        assert!(
+            !lock_scripts[..i].contains(lock_script),
+            "locks shall differ in contract or args"
+        );
+    }
     let always_success_script = context
         .build_script(&always_success_out_point, Bytes::new())
         .expect("script");
 
     // prepare cells
     let mut inputs = vec![];
-    for _ in 0..rng.gen_range(min_current_group_input_cells..=max_current_group_input_cells) {
-        let input = build_input_cell(&mut context, rng, &lock_script, 0, 200, 200, 100000);
+    for (group, lock_script) in lock_scripts.iter().enumerate() {
+        for _ in 0..rng.gen_range(shape.group_inputs.clone()) {
+            let input = build_input_cell(
+                &mut context,
+                rng,
+                lock_script,
+                shape.group_input_data.clone(),
+                shape.group_input_capacity.clone(),
+            );
+            if group_script == GroupScript::Type {
+                attach_type_script(&mut context, &input, &always_success_script);
+            }
 
-        inputs.push((input, true));
+            inputs.push((input, Some(group)));
+        }
     }
-    for _ in 0..rng.gen_range(1..=6) {
+    for _ in 0..rng.gen_range(shape.other_inputs.clone()) {
         let input = build_input_cell(
             &mut context,
             rng,
             &always_success_script,
-            0,
-            150,
-            150,
-            20000,
+            shape.other_input_data.clone(),
+            shape.other_input_capacity.clone(),
         );
 
-        inputs.push((input, false));
+        inputs.push((input, None));
     }
-    inputs.shuffle(rng);
+    rng.shuffle(&mut inputs);
 
     let mut outputs = vec![];
     let mut outputs_data = vec![];
-    for _ in 0..rng.gen_range(3..=6) {
-        let (output, data) = build_output_cell(rng, &lock_script, 0, 300, 2000, 30000);
+    for _ in 0..rng.gen_range(shape.outputs.clone()) {
+        let (output, data) = build_output_cell(
+            rng,
+            &lock_scripts[0],
+            shape.output_data.clone(),
+            shape.output_capacity.clone(),
+        );
+        let output = match group_script {
+            GroupScript::Lock => output,
+            GroupScript::Type => output
+                .as_builder()
+                .lock(always_success_script.clone())
+                .type_(Some(lock_scripts[0].clone()).pack())
+                .build(),
+        };
 
         outputs.push(output);
         outputs_data.push(data);
     }
 
     // Prepare just enough witness
-    let indices: Vec<_> = inputs
-        .iter()
-        .enumerate()
-        .filter(|(_, (_, f))| *f)
-        .map(|(i, _)| i)
+    let groups: Vec<Vec<usize>> = (0..locks.len())
+        .map(|group| {
+            inputs
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, g))| *g == Some(group))
+                .map(|(i, _)| i)
+                .collect()
+        })
         .collect();
-    let first_witness_index = indices[0];
-    let mut witnesses = vec![Bytes::new(); first_witness_index + 1];
-    witnesses[first_witness_index] = WitnessArgs::new_builder()
-        .lock(Some(Bytes::from(vec![0u8; 32])).pack())
-        .build()
-        .as_bytes();
+    let last_first_witness_index = groups.iter().map(|indices| indices[0]).max().unwrap();
+    let mut witnesses = vec![Bytes::new(); last_first_witness_index + 1];
+    for indices in &groups {
+        witnesses[indices[0]] = WitnessArgs::new_builder()
+            .lock(Some(Bytes::from(vec![0u8; 32])).pack())
+            .build()
+            .as_bytes();
+    }
 
     // Build transaction
     let uncompleted_tx = TransactionBuilder::default()
+        .cell_deps(cell_deps)
         .inputs(inputs.into_iter().map(|(i, _)| i))
         .outputs(outputs)
         .outputs_data(outputs_data.pack())
         .witnesses(witnesses.pack())
         .build();
-    let signed_tx = complete_and_sign_tx(&mut context, uncompleted_tx, first_witness_index);
+    let signed_tx =
+        complete_and_sign_groups(&mut context, uncompleted_tx, locks, &groups, group_script);
 
-    (context, signed_tx, indices)
+    (context, signed_tx, groups)
 }
 
 fn complete_and_sign_tx(
     context: &mut Context,
     uncompleted_tx: TransactionView,
     first_witness_index: usize,
+    lock: &LockSetup,
 ) -> TransactionView {
     let unsigned_tx = context.complete_tx(uncompleted_tx);
 
-    let ckb_tx_message = {
-        let unsigned_mock_tx = context.dump_tx(&unsigned_tx).expect("dump tx");
-        let mut hasher = Hasher::default();
-        generate_ckb_tx_message_all_from_mock_tx(
-            &unsigned_mock_tx.into(),
-            ScriptOrIndex::Index(first_witness_index),
-            &mut hasher,
-        )
-        .expect("generate ckb tx message all");
-        hasher.hash()
+    sign_group(
+        context,
+        unsigned_tx,
+        first_witness_index,
+        lock,
+        GroupScript::Lock,
+    )
+}
+
+// Signs each group independently, lock fields of other groups are not part
+// of a group's preimage
+fn complete_and_sign_groups(
+    context: &mut Context,
+    uncompleted_tx: TransactionView,
+    locks: &[&LockSetup],
+    groups: &[Vec<usize>],
+    group_script: GroupScript,
+) -> TransactionView {
+    let unsigned_tx = context.complete_tx(uncompleted_tx);
+
+    locks
+        .iter()
+        .zip(groups)
+        .fold(unsigned_tx, |tx, (lock, indices)| {
+            sign_group(context, tx, indices[0], lock, group_script)
+        })
+}
+
+fn sign_group(
+    context: &Context,
+    unsigned_tx: TransactionView,
+    first_witness_index: usize,
+    lock: &LockSetup,
+    group_script: GroupScript,
+) -> TransactionView {
+    let preimage = match group_script {
+        GroupScript::Lock => generate_preimage_with_sighash_mode(
+            context,
+            &unsigned_tx,
+            first_witness_index,
+            lock.sighash_mode,
+        ),
+        GroupScript::Type => {
+            assert_eq!(
+                lock.sighash_mode,
+                SighashMode::All,
+                "type script groups only support SighashMode::All"
+            );
+            let input = unsigned_tx.inputs().get(first_witness_index).unwrap();
+            let (cell_output, _) = context.get_cell(&input.previous_output()).unwrap();
+            let type_script = cell_output.type_().to_opt().unwrap();
+            generate_type_script_preimage(context, &unsigned_tx, &type_script)
+        }
     };
+    let lock_content = (lock.signer)(&preimage);
+
+    fill_lock(unsigned_tx, first_witness_index, lock_content)
+}
 
-    // Use ckb_tx_message to replace the placeholder part in unsigned transaction
-    let mut witnesses: Vec<_> = unsigned_tx.witnesses().into_iter().collect();
+// Replaces the first witness of current script group, which is a WitnessArgs
+fn map_first_witness<F: FnOnce(WitnessArgs) -> WitnessArgs>(
+    tx: TransactionView,
+    first_witness_index: usize,
+    f: F,
+) -> TransactionView {
+    let mut witnesses: Vec<_> = tx.witnesses().into_iter().collect();
     let first_witness =
         WitnessArgs::from_slice(&witnesses[first_witness_index].raw_data()).unwrap();
-    witnesses[first_witness_index] = first_witness
+    witnesses[first_witness_index] = f(first_witness).as_bytes().pack();
+    tx.as_advanced_builder().set_witnesses(witnesses).build()
+}
+
+// Use ckb_tx_message to replace the placeholder part in unsigned transaction
+fn fill_lock(
+    unsigned_tx: TransactionView,
+    first_witness_index: usize,
+    lock_content: Bytes,
+) -> TransactionView {
+    map_first_witness(unsigned_tx, first_witness_index, |first_witness| {
+        first_witness
+            .as_builder()
+            .lock(Some(lock_content).pack())
+            .build()
+    })
+}
+
+// Same as Context::deploy_cell, except that the out point & type id args are
+// drawn from StableRng seeded by the first 8 bytes(little endian) of the data
+// hash, rather than from rand's StdRng, so a seed always leads to the same
+// tx
+fn deploy_cell(context: &mut Context, data: Bytes) -> OutPoint {
+    let data_hash = CellOutput::calc_data_hash(&data);
+    if let Some(out_point) = context.cells_by_data_hash.get(&data_hash) {
+        return out_point.clone();
+    }
+
+    let mut rng = StableRng::seed_from_u64(u64::from_le_bytes(
+        data_hash.as_slice()[..8].try_into().unwrap(),
+    ));
+    let mut tx_hash = [0u8; 32];
+    rng.fill(&mut tx_hash);
+    let mut type_id_args = [0u8; 32];
+    rng.fill(&mut type_id_args);
+
+    let out_point = OutPoint::new_builder().tx_hash(tx_hash.pack()).build();
+    let type_id_script = Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(type_id_args.as_slice().pack())
+        .build();
+    let cell = CellOutput::new_builder()
+        .type_(Some(type_id_script).pack())
+        .build_exact_capacity(Capacity::bytes(data.len()).unwrap())
+        .unwrap();
+    context.create_cell_with_out_point(out_point.clone(), cell, data);
+    out_point
+}
+
+// Moves the lock script of +input+'s cell to its type script, using
+// +lock_script+ as the new lock script instead
+fn attach_type_script(context: &mut Context, input: &CellInput, lock_script: &Script) {
+    let cell = context.cells.get_mut(&input.previous_output()).unwrap();
+    let type_script = cell.0.lock();
+    cell.0 = cell
+        .0
+        .clone()
         .as_builder()
-        .lock(Some(Bytes::from(ckb_tx_message.to_vec())).pack())
-        .build()
-        .as_bytes()
-        .pack();
-    unsigned_tx
-        .as_advanced_builder()
-        .set_witnesses(witnesses)
-        .build()
+        .lock(lock_script.clone())
+        .type_(Some(type_script).pack())
+        .build();
 }
 
-fn build_input_cell<R: Rng>(
+fn build_input_cell(
     context: &mut Context,
-    rng: &mut R,
+    rng: &mut StableRng,
     script: &Script,
-    min_data_length: usize,
-    max_data_length: usize,
-    min_capacity_bytes: usize,
-    max_capacity_bytes: usize,
+    data_length: RangeInclusive<usize>,
+    capacity_bytes: RangeInclusive<usize>,
 ) -> CellInput {
-    let data_length = rng.gen_range(min_data_length..=max_data_length);
+    let data_length = rng.gen_range(data_length);
     let data = random_data(rng, data_length);
 
-    let capacity = (data_length + rng.gen_range(min_capacity_bytes..=max_capacity_bytes))
-        * 100_000_000
-        + rng.gen_range(0..100_000_000);
+    let capacity =
+        (data_length + rng.gen_range(capacity_bytes)) * 100_000_000 + rng.gen_range(0..100_000_000);
 
-    let input_out_point = context.create_cell(
+    // Context::create_cell picks out points from a non-deterministic rng,
+    // an out point is drawn from the seeded rng instead, so a seed always
+    // leads to the same tx
+    let mut tx_hash = [0u8; 32];
+    rng.fill(&mut tx_hash);
+    let input_out_point = OutPoint::new_builder()
+        .tx_hash(tx_hash.pack())
+        .index(0u32.pack())
+        .build();
+    context.create_cell_with_out_point(
+        input_out_point.clone(),
         CellOutput::new_builder()
             .capacity((capacity as u64).pack())
             .lock(script.clone())
@@ -346,22 +1159,19 @@ fn build_input_cell<R: Rng>(
         .build()
 }
 
-fn build_output_cell<R: Rng>(
-    rng: &mut R,
+fn build_output_cell(
+    rng: &mut StableRng,
     script: &Script,
-    min_data_length: usize,
-    max_data_length: usize,
-    min_capacity_bytes: usize,
-    max_capacity_bytes: usize,
+    data_length: RangeInclusive<usize>,
+    capacity_bytes: RangeInclusive<usize>,
 ) -> (CellOutput, Bytes) {
-    let data_length = rng.gen_range(min_data_length..=max_data_length);
+    let data_length = rng.gen_range(data_length);
     let mut data = vec![0u8; data_length];
     rng.fill(&mut data[..]);
     let data: Bytes = data.into();
 
-    let capacity = (data_length + rng.gen_range(min_capacity_bytes..=max_capacity_bytes))
-        * 100_000_000
-        + rng.gen_range(0..100_000_000);
+    let capacity =
+        (data_length + rng.gen_range(capacity_bytes)) * 100_000_000 + rng.gen_range(0..100_000_000);
 
     (
         CellOutput::new_builder()
@@ -372,7 +1182,7 @@ fn build_output_cell<R: Rng>(
     )
 }
 
-fn random_data<R: Rng>(rng: &mut R, length: usize) -> Bytes {
+fn random_data(rng: &mut StableRng, length: usize) -> Bytes {
     let mut data = vec![0u8; length];
     rng.fill(&mut data[..]);
     data.into()
@@ -0,0 +1,126 @@
+//! A pinned PRNG for building transactions, so a seed maps to the same
+//! vector forever. `rand::rngs::StdRng` is not used, since its algorithm, as
+//! well as sampling routines such as `gen_range`, may change across rand
+//! releases.
+//!
+//! The algorithm is fully specified here, so it can also be reimplemented
+//! in other languages:
+//!
+//! * Seeding: the 4 words of state are 4 successive outputs of
+//!   [SplitMix64](https://prng.di.unimi.it/splitmix64.c), starting from the
+//!   seed
+//! * Generation: [xoshiro256**](https://prng.di.unimi.it/xoshiro256starstar.c)
+//! * Ranges: see [StableRng::gen_range]
+//! * Bytes: see [StableRng::fill]
+//! * Shuffling: see [StableRng::shuffle]
+use core::ops::{Bound, RangeBounds};
+
+#[derive(Clone, Debug)]
+pub struct StableRng {
+    state: [u64; 4],
+}
+
+impl StableRng {
+    pub fn seed_from_u64(seed: u64) -> Self {
+        let mut x = seed;
+        let mut splitmix64 = || {
+            x = x.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+        StableRng {
+            state: [splitmix64(), splitmix64(), splitmix64(), splitmix64()],
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    /// Picks a value uniformly from +range+. With span being the number of
+    /// values in the range, outputs less than 2^64 mod span are rejected,
+    /// then the lower bound plus the output mod span is picked. Panics when
+    /// the range is empty.
+    pub fn gen_range<R: RangeBounds<usize>>(&mut self, range: R) -> usize {
+        let low = match range.start_bound() {
+            Bound::Included(low) => *low as u64,
+            Bound::Excluded(low) => *low as u64 + 1,
+            Bound::Unbounded => 0,
+        };
+        let high = match range.end_bound() {
+            Bound::Included(high) => *high as u64,
+            Bound::Excluded(high) => (*high as u64).checked_sub(1).expect("empty range"),
+            Bound::Unbounded => usize::MAX as u64,
+        };
+        assert!(low <= high, "empty range");
+
+        let span = (high - low).wrapping_add(1);
+        if span == 0 {
+            return self.next_u64() as usize;
+        }
+        let threshold = span.wrapping_neg() % span;
+        loop {
+            let value = self.next_u64();
+            if value >= threshold {
+                return (low + value % span) as usize;
+            }
+        }
+    }
+
+    /// Fills +dest+ with bytes of successive outputs in little endian, the
+    /// unused bytes of the last output are dropped.
+    pub fn fill(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    /// Fisher-Yates shuffle: for i from the last index down to 1, swaps the
+    /// i-th element with the element at `gen_range(0..=i)`.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.gen_range(0..=i);
+            slice.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Published vectors are identified by seeds, outputs of StableRng shall
+    // never change
+    #[test]
+    fn test_stable_rng_outputs_are_pinned() {
+        let mut rng = StableRng::seed_from_u64(0);
+        assert_eq!(
+            [rng.next_u64(), rng.next_u64(), rng.next_u64()],
+            [0x99ec5f36cb75f2b4, 0xbf6e1f784956452a, 0x1a5f849d4933e6e0]
+        );
+        assert_eq!(rng.gen_range(3..=6), 3);
+        assert_eq!(rng.gen_range(0..100_000_000), 17723737);
+
+        let mut data = [0u8; 10];
+        rng.fill(&mut data);
+        assert_eq!(data, [202, 202, 235, 217, 117, 131, 239, 255, 152, 76]);
+
+        let mut items = [0, 1, 2, 3, 4];
+        rng.shuffle(&mut items);
+        assert_eq!(items, [2, 0, 4, 1, 3]);
+    }
+}
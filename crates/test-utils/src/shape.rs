@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Shape of built transactions, each range is inclusive, and is kept as a
+/// `[min, max]` pair when serialized. Data lengths are in bytes, capacities
+/// are in CKBytes on top of occupied data, plus a random amount of shannons.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TxShape {
+    /// Number of input cells in current script group
+    #[serde(with = "inclusive_range")]
+    pub group_inputs: RangeInclusive<usize>,
+    #[serde(with = "inclusive_range")]
+    pub group_input_data: RangeInclusive<usize>,
+    #[serde(with = "inclusive_range")]
+    pub group_input_capacity: RangeInclusive<usize>,
+    /// Number of always success input cells
+    #[serde(with = "inclusive_range")]
+    pub other_inputs: RangeInclusive<usize>,
+    #[serde(with = "inclusive_range")]
+    pub other_input_data: RangeInclusive<usize>,
+    #[serde(with = "inclusive_range")]
+    pub other_input_capacity: RangeInclusive<usize>,
+    #[serde(with = "inclusive_range")]
+    pub outputs: RangeInclusive<usize>,
+    #[serde(with = "inclusive_range")]
+    pub output_data: RangeInclusive<usize>,
+    #[serde(with = "inclusive_range")]
+    pub output_capacity: RangeInclusive<usize>,
+    /// Number of witnesses beyond input cells, only for txs with witness data
+    #[serde(with = "inclusive_range")]
+    pub extra_witnesses: RangeInclusive<usize>,
+    /// Lengths of input_type & output_type in the first witness of current
+    /// script group, and of other witnesses, only for txs with witness data
+    #[serde(with = "inclusive_range")]
+    pub witness_length: RangeInclusive<usize>,
+    /// Data length of input cells replaced with large data, only for txs
+    /// with super large data
+    #[serde(with = "inclusive_range")]
+    pub large_input_data: RangeInclusive<usize>,
+}
+
+impl TxShape {
+    /// Shape of build_bare_tx
+    pub fn bare() -> Self {
+        TxShape {
+            group_inputs: 1..=5,
+            group_input_data: 0..=200,
+            group_input_capacity: 200..=100000,
+            other_inputs: 1..=6,
+            other_input_data: 0..=150,
+            other_input_capacity: 150..=20000,
+            outputs: 3..=6,
+            output_data: 0..=300,
+            output_capacity: 2000..=30000,
+            extra_witnesses: 1..=3,
+            witness_length: 10..=200,
+            large_input_data: 70000..=300000,
+        }
+    }
+
+    /// Shape of build_bare_tx_multiple_input_cells
+    pub fn multiple_input() -> Self {
+        TxShape {
+            group_inputs: 3..=5,
+            ..Self::bare()
+        }
+    }
+
+    /// Shape of build_tx_with_witness_data
+    pub fn witness() -> Self {
+        TxShape {
+            group_inputs: 3..=5,
+            ..Self::bare()
+        }
+    }
+
+    /// Shape of build_tx_with_super_large_data
+    pub fn large_data() -> Self {
+        TxShape {
+            group_inputs: 2..=4,
+            witness_length: 70000..=300000,
+            ..Self::bare()
+        }
+    }
+
+    /// Ensures all ranges are non-empty, and there is at least one input
+    /// cell in current script group
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, range) in [
+            ("group_inputs", &self.group_inputs),
+            ("group_input_data", &self.group_input_data),
+            ("group_input_capacity", &self.group_input_capacity),
+            ("other_inputs", &self.other_inputs),
+            ("other_input_data", &self.other_input_data),
+            ("other_input_capacity", &self.other_input_capacity),
+            ("outputs", &self.outputs),
+            ("output_data", &self.output_data),
+            ("output_capacity", &self.output_capacity),
+            ("extra_witnesses", &self.extra_witnesses),
+            ("witness_length", &self.witness_length),
+            ("large_input_data", &self.large_input_data),
+        ] {
+            if range.is_empty() {
+                return Err(format!("{} is an empty range", name));
+            }
+        }
+        if *self.group_inputs.start() == 0 {
+            return Err("group_inputs shall start from at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+mod inclusive_range {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::ops::RangeInclusive;
+
+    pub fn serialize<S: Serializer>(
+        range: &RangeInclusive<usize>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        [*range.start(), *range.end()].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<RangeInclusive<usize>, D::Error> {
+        let [start, end] = <[usize; 2]>::deserialize(deserializer)?;
+        Ok(start..=end)
+    }
+}
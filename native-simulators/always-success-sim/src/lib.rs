@@ -0,0 +1 @@
+ckb_std::entry_simulator!(always_success::program_entry);
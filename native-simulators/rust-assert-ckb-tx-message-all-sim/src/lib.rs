@@ -0,0 +1 @@
+ckb_std::entry_simulator!(rust_assert_ckb_tx_message_all::program_entry);
@@ -10,6 +10,12 @@ use std::env;
 use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
+use test_utils::script_error_code;
+
+#[cfg(feature = "native-simulator")]
+mod native_simulator;
+#[cfg(feature = "native-simulator")]
+pub use native_simulator::*;
 
 #[cfg(test)]
 mod tests;
@@ -90,6 +96,20 @@ impl Loader {
     }
 }
 
+// Asserts that verification fails since a script terminates with the exit
+// code, so a negative test cannot pass for a different reason.
+pub fn assert_script_error<C: Into<i8>>(result: Result<Cycle, Error>, exit_code: C) {
+    let exit_code = exit_code.into();
+    let error = result.expect_err("verification shall fail");
+    assert_eq!(
+        script_error_code(&error),
+        Some(exit_code),
+        "expected exit code {}, got {}",
+        exit_code,
+        error
+    );
+}
+
 // This helper method runs Context::verify_tx, but in case error happens,
 // it also dumps current transaction to failed_txs folder.
 pub fn verify_and_dump_failed_tx(
@@ -0,0 +1,183 @@
+//! Runs scripts via native simulators built from native-simulators, so they
+//! can be debugged with breakpoints & measured for line coverage.
+
+use crate::Loader;
+use ckb_mock_tx_types::MockTransaction;
+use ckb_testtool::{
+    ckb_types::{
+        bytes::Bytes,
+        core::{ScriptHashType, TransactionView},
+        packed::{Byte32, CellOutput, Script},
+        prelude::*,
+    },
+    context::Context,
+};
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// Simulators locate the transaction & running setup via process wide
+// environment variables, only one script can run at a time.
+static RUNNING: Mutex<()> = Mutex::new(());
+
+impl Loader {
+    /// Path of the native simulator for a contract, which is built by
+    /// `make build` as lib<contract>_sim in cargo's target directory, under
+    /// the same profile as contracts(MODE, release by default).
+    pub fn simulator_path(&self, name: &str) -> PathBuf {
+        // build/<mode> -> target/<mode>
+        let profile = self.0.file_name().expect("build mode");
+        let target_dir = match env::var("CARGO_TARGET_DIR") {
+            Ok(val) => PathBuf::from(val),
+            Err(_) => self.0.join("../../target"),
+        };
+        target_dir.join(profile).join(format!(
+            "lib{}_sim.{}",
+            name.replace('-', "_"),
+            env::consts::DLL_EXTENSION
+        ))
+    }
+}
+
+/// Native simulators keyed by code hashes(data hash of contract binaries)
+#[derive(Default)]
+pub struct Simulators(HashMap<Byte32, PathBuf>);
+
+impl Simulators {
+    pub fn simulator(mut self, contract_bin: &Bytes, path: PathBuf) -> Self {
+        assert!(path.is_file(), "Simulator {:?} is missing!", path);
+        self.0
+            .insert(CellOutput::calc_data_hash(contract_bin), path);
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum SimulatorError {
+    /// Script code is not backed by any simulator
+    MissingSimulator(Script),
+    /// Script terminates with a non-zero exit code
+    Script(Script, i8),
+}
+
+/// Runs all script groups of the transaction natively. Unlike
+/// Context::verify_tx with ckb-testtool's native-simulator feature, exit
+/// codes of scripts are checked, and a script without simulator fails the
+/// verification instead of falling back to CKB-VM.
+pub fn verify_tx_natively(
+    context: &Context,
+    tx: &TransactionView,
+    simulators: &Simulators,
+) -> Result<(), SimulatorError> {
+    let repr_mock_tx = context.dump_tx(tx).expect("dump tx");
+    let mock_tx: MockTransaction = repr_mock_tx.clone().into();
+    let input_cells: Vec<CellOutput> = mock_tx
+        .mock_info
+        .inputs
+        .iter()
+        .map(|input| input.output.clone())
+        .collect();
+
+    // Script, is lock script, is output, script index, in the order of CKB
+    let mut groups: Vec<(Script, bool, bool, usize)> = vec![];
+    let mut add_group = |script: Script, is_lock: bool, is_output: bool, index: usize| {
+        if !groups.iter().any(|g| g.0 == script && g.1 == is_lock) {
+            groups.push((script, is_lock, is_output, index));
+        }
+    };
+    for (i, cell) in input_cells.iter().enumerate() {
+        add_group(cell.lock(), true, false, i);
+    }
+    for (i, cell) in input_cells.iter().enumerate() {
+        if let Some(type_script) = cell.type_().to_opt() {
+            add_group(type_script, false, false, i);
+        }
+    }
+    for (i, cell) in tx.outputs().into_iter().enumerate() {
+        if let Some(type_script) = cell.type_().to_opt() {
+            add_group(type_script, false, true, i);
+        }
+    }
+
+    let native_binaries: HashMap<String, String> = simulators
+        .0
+        .iter()
+        .map(|(code_hash, path)| {
+            // code hash, hash type(0xff for any), offset & length in cell data
+            let key = [code_hash.as_slice(), &[0xff], &[0u8; 8]].concat();
+            (
+                format!("0x{}", to_hex(&key)),
+                path.to_str().expect("utf8 path").to_string(),
+            )
+        })
+        .collect();
+
+    let _running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    let tmp_dir = env::temp_dir().join(format!("ckb-tx-message-all-simulator-{}", tx.hash()));
+    std::fs::create_dir_all(&tmp_dir).expect("create tmp dir");
+    let tx_file = tmp_dir.join("tx.json");
+    std::fs::write(
+        &tx_file,
+        serde_json::to_string(&repr_mock_tx).expect("tx to json"),
+    )
+    .expect("write tx file");
+    env::set_var("CKB_TX_FILE", &tx_file);
+
+    let result =
+        groups
+            .into_iter()
+            .try_for_each(|(script, is_lock_script, is_output, script_index)| {
+                let path = code_data_hash(context, &script)
+                    .and_then(|data_hash| simulators.0.get(&data_hash))
+                    .ok_or_else(|| SimulatorError::MissingSimulator(script.clone()))?;
+
+                let setup_file = tmp_dir.join("setup.json");
+                let setup = serde_json::json!({
+                    "is_lock_script": is_lock_script,
+                    "is_output": is_output,
+                    "script_index": script_index,
+                    "vm_version": 2,
+                    "native_binaries": native_binaries,
+                    "run_type": "DynamicLib",
+                });
+                std::fs::write(&setup_file, setup.to_string()).expect("write setup file");
+                env::set_var("CKB_RUNNING_SETUP", &setup_file);
+
+                match unsafe { run_simulator(path) } {
+                    0 => Ok(()),
+                    code => Err(SimulatorError::Script(script, code)),
+                }
+            });
+    std::fs::remove_dir_all(&tmp_dir).expect("remove tmp dir");
+    result
+}
+
+// Scripts built via Context::build_script refer to code cells by type id
+fn code_data_hash(context: &Context, script: &Script) -> Option<Byte32> {
+    if script.hash_type() == ScriptHashType::Type.into() {
+        let out_point = context.cells_by_type_hash.get(&script.code_hash())?;
+        let (_, data) = context.cells.get(out_point)?;
+        Some(CellOutput::calc_data_hash(data))
+    } else {
+        Some(script.code_hash())
+    }
+}
+
+// Simulators are loaded afresh for each script, since the transaction &
+// running setup are only read once per loaded library.
+unsafe fn run_simulator(path: &PathBuf) -> i8 {
+    let lib = libloading::Library::new(path).expect("load simulator");
+    let set_script_info: libloading::Symbol<
+        unsafe extern "C" fn(ptr: *const std::ffi::c_void, tx_ctx_id: u64, vm_ctx_id: u64),
+    > = lib.get(b"__set_script_info").expect("__set_script_info");
+    set_script_info(std::ptr::null(), 0, 0);
+
+    let main: libloading::Symbol<unsafe extern "C" fn(argc: i32, argv: *const *const i8) -> i8> =
+        lib.get(b"__ckb_std_main").expect("__ckb_std_main");
+    main(0, std::ptr::null())
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -1,8 +1,14 @@
-use crate::Loader;
-use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionBuilder, packed::*, prelude::*};
+use crate::{assert_script_error, Loader};
+use ckb_testtool::ckb_error::Error;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{Cycle, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
 use ckb_testtool::context::Context;
 use proptest::prelude::*;
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use test_utils::*;
 
 fn _test_valid_bare_tx(contract_name: &str, seed: u64) {
@@ -29,6 +35,11 @@ proptest! {
         _test_valid_bare_tx("c-assert-ckb-tx-message-all", seed);
     }
 
+    #[test]
+    fn test_c_linked_assert_ckb_tx_message_on_valid_bare_tx(seed: u64) {
+        _test_valid_bare_tx("c-linked-assert-ckb-tx-message-all", seed);
+    }
+
 }
 
 fn _test_valid_tx_with_witness(contract_name: &str, seed: u64) {
@@ -50,6 +61,11 @@ proptest! {
         _test_valid_tx_with_witness("c-assert-ckb-tx-message-all", seed);
     }
 
+    #[test]
+    fn test_c_linked_assert_ckb_tx_message_on_valid_tx_with_witness(seed: u64) {
+        _test_valid_tx_with_witness("c-linked-assert-ckb-tx-message-all", seed);
+    }
+
     #[test]
     fn test_rust_assert_ckb_tx_message_on_valid_tx_with_witness(seed: u64) {
         _test_valid_tx_with_witness("rust-assert-ckb-tx-message-all", seed);
@@ -81,12 +97,644 @@ proptest! {
         _test_valid_tx_with_super_large_data("c-assert-ckb-tx-message-all", seed);
     }
 
+    #[test]
+    fn test_c_linked_assert_ckb_tx_message_on_valid_tx_with_super_large_data(seed: u64) {
+        _test_valid_tx_with_super_large_data("c-linked-assert-ckb-tx-message-all", seed);
+    }
+
     #[test]
     fn test_rust_assert_ckb_tx_message_on_valid_tx_with_super_large_data(seed: u64) {
         _test_valid_tx_with_super_large_data("rust-assert-ckb-tx-message-all", seed);
     }
 }
 
+// Tiny load buffers take over a billion cycles on the large data txs, so
+// the bench is capped by the max block cycles instead.
+const BENCH_MAX_CYCLES: Cycle = 3_500_000_000;
+const BENCH_BUFFER_LENGTHS: [u32; 5] = [1, 1024, 8 * 1024, 32 * 1024, 128 * 1024];
+const BENCH_SEEDS: [u64; 3] = [0, 1, 2];
+
+fn _test_load_buffer_length(buffer_length: u32, seed: u64) -> Result<Cycle, Error> {
+    let contract_bin: Bytes = Loader::default().load_binary("bench-ckb-tx-message-all");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, _) = build_tx_with_super_large_data_and_args(
+        contract_bin,
+        success_bin,
+        buffer_length.to_le_bytes().to_vec().into(),
+        seed,
+    );
+
+    context.verify_tx(&tx, BENCH_MAX_CYCLES)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    // The in-VM generator shall work on the same large data scenarios as
+    // above, regardless of the load buffer length.
+    #[test]
+    fn test_bench_ckb_tx_message_all_on_load_buffer_lengths(
+        buffer_length in prop::sample::select(BENCH_BUFFER_LENGTHS.to_vec()),
+        seed: u64,
+    ) {
+        _test_load_buffer_length(buffer_length, seed).expect("pass verification");
+    }
+
+    #[test]
+    fn test_bench_ckb_tx_message_all_on_empty_load_buffer(seed: u64) {
+        assert_script_error(_test_load_buffer_length(0, seed), ExitCode::Generation);
+    }
+}
+
+// Runs every load buffer length on the same large data txs, so the printed
+// cycles are comparable across lengths. The table in the README of
+// bench-ckb-tx-message-all is produced by this test.
+#[test]
+fn test_bench_ckb_tx_message_all_cycles() {
+    println!("buffer_length, cycles");
+    for buffer_length in BENCH_BUFFER_LENGTHS {
+        for seed in BENCH_SEEDS {
+            let cycles = _test_load_buffer_length(buffer_length, seed).expect("pass verification");
+            println!("{}, {}", buffer_length, cycles);
+        }
+    }
+}
+
+// Runs a tx locked by dump-ckb-tx-message-all-preimage, asserting the dumped
+// preimage matches the off-chain one, along with traced segments when every
+// group input cell has a witness
+fn _assert_dumped_preimage(mut context: Context, tx: TransactionView, indices: Vec<usize>) {
+    context.set_capture_debug(true);
+
+    // run
+    context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+
+    let dumped = extract_dumped_preimage(&context.captured_messages()).expect("dumped preimage");
+    assert_eq!(dumped, generate_preimage(&context, &tx, indices[0]));
+    if indices.iter().all(|index| *index < tx.witnesses().len()) {
+        _assert_traced_segments(&context, &tx, &indices, &dumped);
+    }
+}
+
+fn _test_dumped_preimage(seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("dump-ckb-tx-message-all-preimage");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, indices) = build_tx_with_witness_data(contract_bin, success_bin, seed);
+    _assert_dumped_preimage(context, tx, indices);
+}
+
+// Segments traced by the dump contract shall tile the dumped preimage in the
+// order of the specification, when every group input cell has a witness.
+fn _assert_traced_segments(
+    context: &Context,
+    tx: &TransactionView,
+    indices: &[usize],
+    dumped: &[u8],
+) {
+    let segments = extract_traced_segments(&context.captured_messages()).expect("segments");
+
+    let inputs = tx.inputs().len();
+    let mut labels = vec!["tx hash".to_string()];
+    for i in 0..inputs {
+        labels.push(format!("input cell {} output", i));
+        labels.push(format!("input cell {} data", i));
+    }
+    labels.push("first group witness input_type".to_string());
+    labels.push("first group witness output_type".to_string());
+    labels.extend((1..indices.len()).map(|n| format!("group witness {}", n)));
+    labels.extend((inputs..tx.witnesses().len()).map(|i| format!("witness {}", i)));
+    assert_eq!(
+        segments.iter().map(|s| s.label.clone()).collect::<Vec<_>>(),
+        labels
+    );
+
+    let mut offset = 0;
+    for segment in &segments {
+        assert_eq!(segment.offset, offset, "segment {}", segment.label);
+        offset += segment.length;
+    }
+    assert_eq!(offset, dumped.len());
+
+    let bytes = |segment: &TracedSegment| &dumped[segment.offset..segment.offset + segment.length];
+    assert_eq!(bytes(&segments[0]), tx.hash().as_slice());
+    for (i, input) in tx.inputs().into_iter().enumerate() {
+        let (_, data) = context
+            .get_cell(&input.previous_output())
+            .expect("input cell");
+        let mut expected = (data.len() as u32).to_le_bytes().to_vec();
+        expected.extend_from_slice(&data);
+        assert_eq!(bytes(&segments[2 + 2 * i]), expected);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_dumped_preimage_matches_off_chain_preimage(seed: u64) {
+        _test_dumped_preimage(seed);
+    }
+}
+
+// Message versions are kept as u8 in .version files & manifests, an unknown
+// value shall be rejected rather than read as another revision.
+#[test]
+fn test_message_version_round_trips_via_u8() {
+    for value in 0..=u8::MAX {
+        match MessageVersion::try_from(value) {
+            Ok(version) => assert_eq!(u8::from(version), value),
+            Err(e) => assert_eq!(e.0, value),
+        }
+    }
+    assert_eq!(
+        MessageVersion::try_from(u8::from(MessageVersion::LATEST)),
+        Ok(MessageVersion::LATEST)
+    );
+    assert!(MessageVersion::try_from(0).is_err());
+    assert!(MessageVersion::try_from(u8::from(MessageVersion::LATEST) + 1).is_err());
+}
+
+fn _test_fixture(seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("dump-ckb-tx-message-all-preimage");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (mut context, tx, indices) = build_tx_with_witness_data(contract_bin, success_bin, seed);
+    context.set_capture_debug(true);
+
+    // run
+    context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+    let dumped = extract_dumped_preimage(&context.captured_messages()).expect("dumped preimage");
+
+    let fixture = generate_fixture(
+        &context,
+        &tx,
+        &indices,
+        MessageVersion::V1,
+        SighashMode::All,
+    );
+    let loaded = Fixture::from_json(&fixture.to_json()).expect("load fixture");
+    assert_eq!(loaded, fixture);
+    loaded.check().expect("check fixture");
+
+    // Loaded fixture carries everything needed to regenerate the preimage
+    assert_eq!(
+        loaded.transaction().unwrap().as_bytes(),
+        tx.data().as_bytes()
+    );
+    assert_eq!(loaded.group, indices);
+    assert_eq!(loaded.preimage().unwrap(), Some(dumped.clone()));
+    let mut hasher = Hasher::new(HashAlgorithm::CkbBlake2b);
+    hasher.update(&dumped);
+    assert_eq!(
+        loaded.hashes["blake2b"],
+        format!("{:x}", Bytes::from(hasher.hash().to_vec()))
+    );
+
+    // Tampering with an input cell is caught
+    let mut tampered = loaded.clone();
+    tampered.inputs[indices[0]].data.push_str("00");
+    assert!(tampered.check().is_err());
+
+    // So is a group lacking input cells of the same lock, or containing
+    // others
+    let mut partial = loaded.clone();
+    match (0..tx.inputs().len()).find(|i| !indices.contains(i)) {
+        Some(other) => {
+            partial.group.push(other);
+            partial.group.sort();
+        }
+        None => {
+            partial.group.pop();
+        }
+    }
+    assert!(matches!(
+        partial.check(),
+        Err(FixtureError::Mismatch("group"))
+    ));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_fixture_round_trips_and_matches_dumped_preimage(seed: u64) {
+        _test_fixture(seed);
+    }
+}
+
+fn _test_tx_shape(seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("rust-assert-ckb-tx-message-all");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    // Default shapes are the ones built into builders
+    let (_, tx, _) = build_tx_with_witness_data(contract_bin.clone(), success_bin.clone(), seed);
+    let (_, shaped_tx, _) = build_tx_with_witness_data_with_shape(
+        LockSetup::new(contract_bin.clone()),
+        success_bin.clone(),
+        &TxShape::witness(),
+        seed,
+    );
+    assert_eq!(tx.hash(), shaped_tx.hash());
+
+    let shape = TxShape {
+        group_inputs: 6..=8,
+        other_inputs: 0..=0,
+        outputs: 1..=1,
+        extra_witnesses: 0..=0,
+        witness_length: 1000..=1000,
+        ..TxShape::witness()
+    };
+    let (context, tx, indices) = build_tx_with_witness_data_with_shape(
+        LockSetup::new(contract_bin),
+        success_bin,
+        &shape,
+        seed,
+    );
+    assert!(shape.group_inputs.contains(&indices.len()));
+    assert_eq!(tx.inputs().len(), indices.len());
+    assert_eq!(tx.outputs().len(), 1);
+    assert_eq!(tx.witnesses().len(), tx.inputs().len());
+    assert_eq!(tx.witnesses().get(1).unwrap().raw_data().len(), 1000);
+
+    // run
+    context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_tx_with_custom_shape(seed: u64) {
+        _test_tx_shape(seed);
+    }
+}
+
+// Locks of 2 script groups, which differ in the hash algorithm picked by
+// script args, so the same contract can be used in both groups
+fn _multi_group_locks(contract_names: [&str; 2]) -> Vec<LockSetup> {
+    vec![
+        _hash_algorithm_lock(contract_names[0], 0, HashAlgorithm::CkbBlake2b),
+        _hash_algorithm_lock(contract_names[1], 1, HashAlgorithm::Sha256),
+    ]
+}
+
+fn _test_valid_multi_group_tx(contract_names: [&str; 2], seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, groups) =
+        build_multi_group_tx(_multi_group_locks(contract_names), success_bin, seed);
+    assert_eq!(groups.len(), 2);
+    assert!(groups[0].iter().all(|i| !groups[1].contains(i)));
+
+    // run
+    let cycles = context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn _test_tampered_group_witness(contract_names: [&str; 2], seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let (context, tx, groups) =
+        build_multi_group_tx(_multi_group_locks(contract_names), success_bin, seed);
+    let preimages: Vec<_> = groups
+        .iter()
+        .map(|indices| generate_preimage(&context, &tx, indices[0]))
+        .collect();
+
+    // Extend input_type in the first witness of one group
+    let tampered = rng.gen_range(0..groups.len());
+    let tx = {
+        let mut witnesses: Vec<_> = tx.witnesses().into_iter().collect();
+        let index = groups[tampered][0];
+        let witness_args =
+            WitnessArgs::from_slice(&witnesses[index].raw_data()).expect("first witness");
+        let mut input_type = witness_args
+            .input_type()
+            .to_opt()
+            .expect("input type")
+            .raw_data()
+            .to_vec();
+        input_type.push(rng.gen());
+        witnesses[index] = witness_args
+            .as_builder()
+            .input_type(Some(Bytes::from(input_type)).pack())
+            .build()
+            .as_bytes()
+            .pack();
+
+        tx.as_advanced_builder().set_witnesses(witnesses).build()
+    };
+
+    // Only the message of the tampered group changes
+    for (i, indices) in groups.iter().enumerate() {
+        let preimage = generate_preimage(&context, &tx, indices[0]);
+        assert_eq!(preimage != preimages[i], i == tampered);
+    }
+
+    // run to a failure of the tampered group
+    let result = context.verify_tx(&tx, 100_000_000);
+    let error = result
+        .as_ref()
+        .expect_err("verification shall fail")
+        .to_string();
+    let source = format!("Inputs[{}].Lock", groups[tampered][0]);
+    assert!(
+        error.contains(&source),
+        "expected {}, got {}",
+        source,
+        error
+    );
+    assert_script_error(result, ExitCode::HashMismatch);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_valid_multi_group_tx(seed: u64) {
+        _test_valid_multi_group_tx(["rust-assert-ckb-tx-message-all"; 2], seed);
+    }
+
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_valid_multi_group_tx(seed: u64) {
+        _test_valid_multi_group_tx(
+            ["rust-assert-ckb-tx-message-all", "c-assert-ckb-tx-message-all"],
+            seed,
+        );
+    }
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_tampered_group_witness(seed: u64) {
+        _test_tampered_group_witness(["rust-assert-ckb-tx-message-all"; 2], seed);
+    }
+
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_tampered_group_witness(seed: u64) {
+        _test_tampered_group_witness(
+            ["rust-assert-ckb-tx-message-all", "c-assert-ckb-tx-message-all"],
+            seed,
+        );
+    }
+}
+
+const SPARSE_WITNESSES: [SparseWitnesses; 4] = [
+    SparseWitnesses::MissingLaterGroupWitnesses,
+    SparseWitnesses::FewerWitnessesThanInputs,
+    SparseWitnesses::FirstGroupInputLast,
+    SparseWitnesses::MissingFirstGroupWitness,
+];
+
+fn _test_sparse_witnesses(contract_name: &str, layout: SparseWitnesses, seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary(contract_name);
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, indices) =
+        build_tx_with_sparse_witnesses(LockSetup::new(contract_bin), success_bin, layout, seed);
+    let (inputs, witnesses) = (tx.inputs().len(), tx.witnesses().len());
+    match layout {
+        SparseWitnesses::MissingLaterGroupWitnesses => {
+            assert!(indices.len() >= 2);
+            assert_eq!(witnesses, indices[0] + 1);
+        }
+        SparseWitnesses::FewerWitnessesThanInputs => {
+            assert!(witnesses > indices[0] && witnesses < inputs);
+            assert!(indices.iter().any(|index| *index >= witnesses));
+        }
+        SparseWitnesses::FirstGroupInputLast => {
+            assert_eq!(indices, vec![inputs - 1]);
+            assert!(witnesses >= inputs);
+        }
+        SparseWitnesses::MissingFirstGroupWitness => assert!(witnesses <= indices[0]),
+    }
+
+    // Contracts agree with the off-chain implementation
+    let fixture = generate_fixture(
+        &context,
+        &tx,
+        &indices,
+        MessageVersion::V1,
+        SighashMode::All,
+    );
+    let result = context.verify_tx(&tx, 100_000_000);
+    match layout {
+        SparseWitnesses::MissingFirstGroupWitness => {
+            assert_eq!(fixture.failure.as_deref(), Some("missing_witness"));
+            assert_script_error(result, ExitCode::Syscall);
+        }
+        _ => {
+            assert_eq!(fixture.failure, None);
+            let cycles = result.expect("pass verification");
+            println!("consume cycles: {}", cycles);
+        }
+    }
+}
+
+fn _test_sparse_witnesses_dumped_preimage(layout: SparseWitnesses, seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("dump-ckb-tx-message-all-preimage");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, indices) =
+        build_tx_with_sparse_witnesses(LockSetup::new(contract_bin), success_bin, layout, seed);
+    _assert_dumped_preimage(context, tx, indices);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_sparse_witnesses(seed: u64, layout in 0usize..4) {
+        _test_sparse_witnesses("rust-assert-ckb-tx-message-all", SPARSE_WITNESSES[layout], seed);
+    }
+
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_sparse_witnesses(seed: u64, layout in 0usize..4) {
+        _test_sparse_witnesses("c-assert-ckb-tx-message-all", SPARSE_WITNESSES[layout], seed);
+    }
+
+    #[test]
+    fn test_dumped_preimage_matches_off_chain_preimage_on_sparse_witnesses(seed: u64, layout in 0usize..3) {
+        _test_sparse_witnesses_dumped_preimage(SPARSE_WITNESSES[layout], seed);
+    }
+}
+
+fn _witness_args_fields(lock: usize, input_type: usize, output_type: usize) -> WitnessArgsFields {
+    WitnessArgsFields {
+        lock: FieldContent::ALL[lock],
+        input_type: FieldContent::ALL[input_type],
+        output_type: FieldContent::ALL[output_type],
+    }
+}
+
+fn _test_witness_args_fields(contract_name: &str, fields: WitnessArgsFields, seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary(contract_name);
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, indices) =
+        build_tx_with_witness_args_fields(LockSetup::new(contract_bin), success_bin, fields, seed);
+    let witness_args =
+        WitnessArgs::from_slice(&tx.witnesses().get(indices[0]).unwrap().raw_data()).unwrap();
+    for (content, field) in [
+        (fields.lock, witness_args.lock()),
+        (fields.input_type, witness_args.input_type()),
+        (fields.output_type, witness_args.output_type()),
+    ] {
+        match content {
+            FieldContent::None => assert!(field.is_none()),
+            FieldContent::Empty => assert!(field.to_opt().unwrap().is_empty()),
+            FieldContent::Data => assert!(!field.to_opt().unwrap().is_empty()),
+        }
+    }
+
+    // A message can always be generated, while the lock field decides
+    // whether the tx passes
+    let fixture = generate_fixture(
+        &context,
+        &tx,
+        &indices,
+        MessageVersion::V1,
+        SighashMode::All,
+    );
+    assert_eq!(fixture.failure, None);
+    let result = context.verify_tx(&tx, 100_000_000);
+    match fields.lock {
+        FieldContent::None => assert_script_error(result, ExitCode::MissingLock),
+        FieldContent::Empty => assert_script_error(result, ExitCode::WrongLockLength),
+        FieldContent::Data => {
+            let cycles = result.expect("pass verification");
+            println!("consume cycles: {}", cycles);
+        }
+    }
+}
+
+fn _test_witness_args_fields_messages(seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("rust-assert-ckb-tx-message-all");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let preimage = |fields: WitnessArgsFields| {
+        let (context, tx, indices) = build_tx_with_witness_args_fields(
+            LockSetup::new(contract_bin.clone()),
+            success_bin.clone(),
+            fields,
+            seed,
+        );
+        generate_preimage(&context, &tx, indices[0])
+    };
+
+    let mut preimages = std::collections::HashMap::new();
+    for input_type in 0..3 {
+        for output_type in 0..3 {
+            let fields = _witness_args_fields(2, input_type, output_type);
+            preimages.insert((input_type, output_type), preimage(fields));
+        }
+    }
+
+    // The lock field is not part of the message
+    for lock in 0..2 {
+        assert_eq!(
+            preimage(_witness_args_fields(lock, 2, 2)),
+            preimages[&(2, 2)]
+        );
+    }
+
+    // All combinations lead to different messages. None hashes an empty
+    // BytesOpt, while Some(empty) hashes the 4-byte header of empty Bytes.
+    let distinct: std::collections::HashSet<_> = preimages.values().collect();
+    assert_eq!(distinct.len(), 9);
+    for other in 0..3 {
+        assert_eq!(
+            preimages[&(1, other)].len(),
+            preimages[&(0, other)].len() + 4
+        );
+        assert_eq!(
+            preimages[&(other, 1)].len(),
+            preimages[&(other, 0)].len() + 4
+        );
+    }
+}
+
+fn _test_witness_args_fields_dumped_preimage(fields: WitnessArgsFields, seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("dump-ckb-tx-message-all-preimage");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, indices) =
+        build_tx_with_witness_args_fields(LockSetup::new(contract_bin), success_bin, fields, seed);
+    _assert_dumped_preimage(context, tx, indices);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_witness_args_fields(
+        seed: u64,
+        lock in 0usize..3,
+        input_type in 0usize..3,
+        output_type in 0usize..3,
+    ) {
+        _test_witness_args_fields(
+            "rust-assert-ckb-tx-message-all",
+            _witness_args_fields(lock, input_type, output_type),
+            seed,
+        );
+    }
+
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_witness_args_fields(
+        seed: u64,
+        lock in 0usize..3,
+        input_type in 0usize..3,
+        output_type in 0usize..3,
+    ) {
+        _test_witness_args_fields(
+            "c-assert-ckb-tx-message-all",
+            _witness_args_fields(lock, input_type, output_type),
+            seed,
+        );
+    }
+
+    #[test]
+    fn test_witness_args_fields_lead_to_different_messages(seed: u64) {
+        _test_witness_args_fields_messages(seed);
+    }
+
+    #[test]
+    fn test_dumped_preimage_matches_off_chain_preimage_on_witness_args_fields(
+        seed: u64,
+        input_type in 0usize..3,
+        output_type in 0usize..3,
+    ) {
+        _test_witness_args_fields_dumped_preimage(
+            _witness_args_fields(2, input_type, output_type),
+            seed,
+        );
+    }
+}
+
 fn _test_unsigned_input_amount_bare_tx(contract_name: &str, seed: u64) {
     let contract_bin: Bytes = Loader::default().load_binary(contract_name);
     let success_bin: Bytes = Loader::default().load_binary("always-success");
@@ -110,7 +758,7 @@ fn _test_unsigned_input_amount_bare_tx(contract_name: &str, seed: u64) {
     }
 
     // run to a failure
-    context.verify_tx(&tx, 10_000_000).unwrap_err();
+    assert_script_error(context.verify_tx(&tx, 10_000_000), ExitCode::HashMismatch);
 }
 
 proptest! {
@@ -148,7 +796,7 @@ fn _test_unsigned_input_cell_data_bare_tx(contract_name: &str, seed: u64) {
     }
 
     // run to a failure
-    context.verify_tx(&tx, 10_000_000).unwrap_err();
+    assert_script_error(context.verify_tx(&tx, 10_000_000), ExitCode::HashMismatch);
 }
 
 proptest! {
@@ -169,15 +817,17 @@ fn _test_unsigned_tx_data_bare_tx(contract_name: &str, seed: u64) {
 
     let (context, tx, _) = build_bare_tx(contract_bin, success_bin, seed);
 
-    // Modify one particular output cell so tx changes
+    // Modify one particular output cell so tx changes, a matching output
+    // data keeps the tx well formed so the failure comes from the script
     let tx = {
         tx.as_advanced_builder()
             .output(CellOutput::new_builder().build())
+            .output_data(Bytes::new().pack())
             .build()
     };
 
     // run to a failure
-    context.verify_tx(&tx, 10_000_000).unwrap_err();
+    assert_script_error(context.verify_tx(&tx, 10_000_000), ExitCode::HashMismatch);
 }
 
 proptest! {
@@ -190,6 +840,11 @@ proptest! {
     fn test_c_assert_ckb_tx_message_on_unsigned_tx_data_bare_tx(seed: u64) {
         _test_unsigned_tx_data_bare_tx("c-assert-ckb-tx-message-all", seed);
     }
+
+    #[test]
+    fn test_c_linked_assert_ckb_tx_message_on_unsigned_tx_data_bare_tx(seed: u64) {
+        _test_unsigned_tx_data_bare_tx("c-linked-assert-ckb-tx-message-all", seed);
+    }
 }
 
 fn _test_ckb_tx_message_on_appended_witness_bare_tx(contract_name: &str, seed: u64) {
@@ -212,7 +867,7 @@ fn _test_ckb_tx_message_on_appended_witness_bare_tx(contract_name: &str, seed: u
     };
 
     // run to a failure
-    context.verify_tx(&tx, 10_000_000).unwrap_err();
+    assert_script_error(context.verify_tx(&tx, 10_000_000), ExitCode::HashMismatch);
 }
 
 proptest! {
@@ -225,6 +880,11 @@ proptest! {
     fn test_c_assert_ckb_tx_message_on_appended_witness_bare_tx(seed: u64) {
         _test_ckb_tx_message_on_appended_witness_bare_tx("c-assert-ckb-tx-message-all", seed);
     }
+
+    #[test]
+    fn test_c_linked_assert_ckb_tx_message_on_appended_witness_bare_tx(seed: u64) {
+        _test_ckb_tx_message_on_appended_witness_bare_tx("c-linked-assert-ckb-tx-message-all", seed);
+    }
 }
 
 fn _test_ckb_tx_message_on_invalid_witness_bare_tx(contract_name: &str, seed: u64) {
@@ -252,7 +912,10 @@ fn _test_ckb_tx_message_on_invalid_witness_bare_tx(contract_name: &str, seed: u6
     };
 
     // run to a failure
-    context.verify_tx(&tx, 10_000_000).unwrap_err();
+    assert_script_error(
+        context.verify_tx(&tx, 10_000_000),
+        ExitCode::InvalidWitnessArgs,
+    );
 }
 
 proptest! {
@@ -265,6 +928,930 @@ proptest! {
     fn test_c_assert_ckb_tx_message_on_invalid_witness_bare_tx(seed: u64) {
         _test_ckb_tx_message_on_invalid_witness_bare_tx("c-assert-ckb-tx-message-all", seed);
     }
+
+    #[test]
+    fn test_c_linked_assert_ckb_tx_message_on_invalid_witness_bare_tx(seed: u64) {
+        _test_ckb_tx_message_on_invalid_witness_bare_tx("c-linked-assert-ckb-tx-message-all", seed);
+    }
+}
+
+// Replaces the lock field of the first witness in the script group
+fn _with_first_witness_lock(
+    tx: TransactionView,
+    index: usize,
+    lock: Option<Bytes>,
+) -> TransactionView {
+    let mut witnesses: Vec<_> = tx.witnesses().into_iter().collect();
+    let witness_args =
+        WitnessArgs::from_slice(&witnesses[index].raw_data()).expect("first witness");
+    let lock = BytesOpt::new_builder().set(lock.map(|l| l.pack())).build();
+    witnesses[index] = witness_args
+        .as_builder()
+        .lock(lock)
+        .build()
+        .as_bytes()
+        .pack();
+
+    tx.as_advanced_builder().set_witnesses(witnesses).build()
+}
+
+fn _test_missing_lock_bare_tx(contract_name: &str, seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary(contract_name);
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, indices) = build_bare_tx(contract_bin, success_bin, seed);
+    let tx = _with_first_witness_lock(tx, indices[0], None);
+
+    // run to a failure
+    assert_script_error(context.verify_tx(&tx, 10_000_000), ExitCode::MissingLock);
+}
+
+fn _test_wrong_lock_length_bare_tx(contract_name: &str, seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary(contract_name);
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, indices) = build_bare_tx(contract_bin, success_bin, seed);
+
+    // Keep the signed hash, but truncate or extend it by one byte
+    let lock = {
+        let witness = tx.witnesses().get(indices[0]).unwrap().raw_data();
+        let mut lock = WitnessArgs::from_slice(&witness)
+            .expect("first witness")
+            .lock()
+            .to_opt()
+            .expect("lock")
+            .raw_data()
+            .to_vec();
+        if seed.is_multiple_of(2) {
+            lock.pop();
+        } else {
+            lock.push(0);
+        }
+        Bytes::from(lock)
+    };
+    let tx = _with_first_witness_lock(tx, indices[0], Some(lock));
+
+    // run to a failure
+    assert_script_error(
+        context.verify_tx(&tx, 10_000_000),
+        ExitCode::WrongLockLength,
+    );
+}
+
+proptest! {
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_missing_lock_bare_tx(seed: u64) {
+        _test_missing_lock_bare_tx("rust-assert-ckb-tx-message-all", seed);
+    }
+
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_missing_lock_bare_tx(seed: u64) {
+        _test_missing_lock_bare_tx("c-assert-ckb-tx-message-all", seed);
+    }
+
+    #[test]
+    fn test_c_linked_assert_ckb_tx_message_on_missing_lock_bare_tx(seed: u64) {
+        _test_missing_lock_bare_tx("c-linked-assert-ckb-tx-message-all", seed);
+    }
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_wrong_lock_length_bare_tx(seed: u64) {
+        _test_wrong_lock_length_bare_tx("rust-assert-ckb-tx-message-all", seed);
+    }
+
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_wrong_lock_length_bare_tx(seed: u64) {
+        _test_wrong_lock_length_bare_tx("c-assert-ckb-tx-message-all", seed);
+    }
+
+    #[test]
+    fn test_c_linked_assert_ckb_tx_message_on_wrong_lock_length_bare_tx(seed: u64) {
+        _test_wrong_lock_length_bare_tx("c-linked-assert-ckb-tx-message-all", seed);
+    }
+}
+
+fn _hash_algorithm_lock(
+    contract_name: &str,
+    args_algorithm: u8,
+    signer: HashAlgorithm,
+) -> LockSetup {
+    let contract_bin: Bytes = Loader::default().load_binary(contract_name);
+
+    LockSetup::new(contract_bin)
+        .args(vec![args_algorithm].into())
+        .signer(hash_signer(signer))
+}
+
+fn _test_valid_tx_with_hash_algorithm(contract_name: &str, algorithm: u8, seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let signer = HashAlgorithm::try_from(algorithm).expect("hash algorithm");
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _hash_algorithm_lock(contract_name, algorithm, signer),
+        success_bin,
+        seed,
+    );
+
+    // run
+    let cycles = context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn _test_mismatched_hash_algorithm(contract_name: &str, algorithm: u8, seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let signer = HashAlgorithm::try_from(algorithm).expect("hash algorithm");
+    // Script args denote a different algorithm from the one used in signing
+    let args_algorithm = (algorithm + 1) % 3;
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _hash_algorithm_lock(contract_name, args_algorithm, signer),
+        success_bin,
+        seed,
+    );
+
+    // run to a failure
+    assert_script_error(context.verify_tx(&tx, 100_000_000), ExitCode::HashMismatch);
+}
+
+fn _test_unknown_hash_algorithm(contract_name: &str, algorithm: u8, seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _hash_algorithm_lock(contract_name, algorithm, HashAlgorithm::CkbBlake2b),
+        success_bin,
+        seed,
+    );
+
+    // run to a failure
+    assert_script_error(
+        context.verify_tx(&tx, 100_000_000),
+        ExitCode::UnknownHashAlgorithm,
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_valid_tx_with_hash_algorithm(seed: u64, algorithm in 0u8..3) {
+        _test_valid_tx_with_hash_algorithm("rust-assert-ckb-tx-message-all", algorithm, seed);
+    }
+
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_valid_tx_with_hash_algorithm(seed: u64, algorithm in 0u8..3) {
+        _test_valid_tx_with_hash_algorithm("c-assert-ckb-tx-message-all", algorithm, seed);
+    }
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_mismatched_hash_algorithm(seed: u64, algorithm in 0u8..3) {
+        _test_mismatched_hash_algorithm("rust-assert-ckb-tx-message-all", algorithm, seed);
+    }
+
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_mismatched_hash_algorithm(seed: u64, algorithm in 0u8..3) {
+        _test_mismatched_hash_algorithm("c-assert-ckb-tx-message-all", algorithm, seed);
+    }
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_unknown_hash_algorithm(seed: u64, algorithm in 3u8..) {
+        _test_unknown_hash_algorithm("rust-assert-ckb-tx-message-all", algorithm, seed);
+    }
+
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_unknown_hash_algorithm(seed: u64, algorithm in 3u8..) {
+        _test_unknown_hash_algorithm("c-assert-ckb-tx-message-all", algorithm, seed);
+    }
+}
+
+fn _sighash_mode_lock(mode: u8) -> LockSetup {
+    let contract_bin: Bytes = Loader::default().load_binary("rust-assert-ckb-tx-message-all");
+
+    let lock =
+        LockSetup::new(contract_bin).args(vec![HashAlgorithm::CkbBlake2b as u8, mode].into());
+    match SighashMode::try_from(mode) {
+        Ok(mode) => lock.sighash_mode(mode),
+        Err(_) => lock,
+    }
+}
+
+// Appends an input cell out of current script group, reusing the lock of an
+// existing one
+fn _append_other_input(
+    context: &mut Context,
+    tx: TransactionView,
+    indices: &[usize],
+) -> TransactionView {
+    let other = (0..tx.inputs().len())
+        .find(|i| !indices.contains(i))
+        .expect("input cell out of current script group");
+    let (cell_output, _) = context
+        .get_cell(&tx.inputs().get(other).unwrap().previous_output())
+        .expect("cell");
+    let out_point = context.create_cell(cell_output, Bytes::new());
+
+    tx.as_advanced_builder()
+        .input(CellInput::new_builder().previous_output(out_point).build())
+        .build()
+}
+
+fn _tamper_output_capacity(tx: TransactionView, index: usize) -> TransactionView {
+    let mut outputs: Vec<_> = tx.outputs().into_iter().collect();
+    let capacity: u64 = outputs[index].capacity().unpack();
+    outputs[index] = outputs[index]
+        .clone()
+        .as_builder()
+        .capacity((capacity - 1).pack())
+        .build();
+
+    tx.as_advanced_builder().set_outputs(outputs).build()
+}
+
+fn _test_valid_tx_with_sighash_mode(mode: u8, seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, _) =
+        build_tx_with_witness_data_with_lock(_sighash_mode_lock(mode), success_bin, seed);
+
+    // run
+    let cycles = context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn _test_sighash_mode_on_appended_input(mode: u8, seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (mut context, tx, indices) =
+        build_tx_with_witness_data_with_lock(_sighash_mode_lock(mode), success_bin, seed);
+    let tx = _append_other_input(&mut context, tx, &indices);
+
+    // Only CKB_TX_MESSAGE_ALL commits to input cells of other groups
+    let result = context.verify_tx(&tx, 100_000_000);
+    match SighashMode::try_from(mode).expect("sighash mode") {
+        SighashMode::All => assert_script_error(result, ExitCode::HashMismatch),
+        _ => {
+            result.expect("pass verification");
+        }
+    }
+}
+
+fn _test_sighash_mode_on_tampered_output(mode: u8, seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, indices) =
+        build_tx_with_witness_data_with_lock(_sighash_mode_lock(mode), success_bin, seed);
+    let mode = SighashMode::try_from(mode).expect("sighash mode");
+    let committed = mode.committed_outputs(&indices, tx.outputs().len());
+
+    for index in 0..tx.outputs().len() {
+        let tampered_tx = _tamper_output_capacity(tx.clone(), index);
+        let result = context.verify_tx(&tampered_tx, 100_000_000);
+        if committed.contains(&index) {
+            assert_script_error(result, ExitCode::HashMismatch);
+        } else {
+            result.expect("pass verification");
+        }
+    }
+}
+
+fn _test_unknown_sighash_mode(contract_name: &str, mode: u8, seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary(contract_name);
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let lock =
+        LockSetup::new(contract_bin).args(vec![HashAlgorithm::CkbBlake2b as u8, mode].into());
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(lock, success_bin, seed);
+
+    // run to a failure
+    assert_script_error(
+        context.verify_tx(&tx, 100_000_000),
+        ExitCode::UnknownSighashMode,
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_valid_tx_with_sighash_mode(seed: u64, mode in 0u8..3) {
+        _test_valid_tx_with_sighash_mode(mode, seed);
+    }
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_appended_input_with_sighash_mode(seed: u64, mode in 0u8..3) {
+        _test_sighash_mode_on_appended_input(mode, seed);
+    }
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_tampered_output_with_sighash_mode(seed: u64, mode in 0u8..3) {
+        _test_sighash_mode_on_tampered_output(mode, seed);
+    }
+
+    #[test]
+    fn test_rust_assert_ckb_tx_message_on_unknown_sighash_mode(seed: u64, mode in 3u8..) {
+        _test_unknown_sighash_mode("rust-assert-ckb-tx-message-all", mode, seed);
+    }
+
+    // The C assert script only supports SighashMode::All
+    #[test]
+    fn test_c_assert_ckb_tx_message_on_unknown_sighash_mode(seed: u64, mode in 1u8..) {
+        _test_unknown_sighash_mode("c-assert-ckb-tx-message-all", mode, seed);
+    }
+}
+
+fn _spawn_oracle_lock(algorithm: HashAlgorithm, named_group: bool) -> LockSetup {
+    _spawn_oracle_lock_with_args(algorithm, |args| {
+        if named_group {
+            args.push(1);
+        }
+    })
+}
+
+// Script args start with the oracle hash & the hash algorithm id, which are
+// then adjusted by +edit+
+fn _spawn_oracle_lock_with_args(
+    algorithm: HashAlgorithm,
+    edit: impl FnOnce(&mut Vec<u8>),
+) -> LockSetup {
+    let contract_bin: Bytes = Loader::default().load_binary("spawn-assert-ckb-tx-message-all");
+    let oracle_bin: Bytes = Loader::default().load_binary("ckb-tx-message-all-oracle");
+
+    let mut args = CellOutput::calc_data_hash(&oracle_bin).as_bytes().to_vec();
+    args.push(algorithm as u8);
+    edit(&mut args);
+
+    LockSetup::new(contract_bin)
+        .args(args.into())
+        .dep_bin(oracle_bin)
+        .signer(hash_signer(algorithm))
+}
+
+fn _test_spawn_oracle_on_valid_tx_with_witness(algorithm: u8, named_group: bool, seed: u64) {
+    let algorithm = HashAlgorithm::try_from(algorithm).expect("hash algorithm");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _spawn_oracle_lock(algorithm, named_group),
+        success_bin,
+        seed,
+    );
+
+    // run
+    let cycles = context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn _test_spawn_oracle_on_unsigned_tx_data(algorithm: u8, named_group: bool, seed: u64) {
+    let algorithm = HashAlgorithm::try_from(algorithm).expect("hash algorithm");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _spawn_oracle_lock(algorithm, named_group),
+        success_bin,
+        seed,
+    );
+
+    // Append an output cell so tx changes, while staying structurally valid
+    let tx = {
+        tx.as_advanced_builder()
+            .output(CellOutput::new_builder().build())
+            .output_data(Bytes::new().pack())
+            .build()
+    };
+
+    // run to a failure
+    assert_script_error(context.verify_tx(&tx, 100_000_000), ExitCode::HashMismatch);
+}
+
+fn _test_spawn_oracle_on_invalid_args(edit: impl FnOnce(&mut Vec<u8>), seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _spawn_oracle_lock_with_args(HashAlgorithm::CkbBlake2b, edit),
+        success_bin,
+        seed,
+    );
+
+    // run to a failure
+    assert_script_error(context.verify_tx(&tx, 100_000_000), ExitCode::InvalidArgs);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_spawn_oracle_on_valid_tx_with_witness(seed: u64, algorithm in 0u8..3) {
+        _test_spawn_oracle_on_valid_tx_with_witness(algorithm, false, seed);
+    }
+
+    #[test]
+    fn test_spawn_oracle_with_named_group_on_valid_tx_with_witness(seed: u64, algorithm in 0u8..3) {
+        _test_spawn_oracle_on_valid_tx_with_witness(algorithm, true, seed);
+    }
+
+    #[test]
+    fn test_spawn_oracle_on_unsigned_tx_data(seed: u64, algorithm in 0u8..3, named_group: bool) {
+        _test_spawn_oracle_on_unsigned_tx_data(algorithm, named_group, seed);
+    }
+
+    #[test]
+    fn test_spawn_oracle_on_invalid_args_length(
+        seed: u64,
+        length in prop_oneof![0usize..33, 35usize..128],
+    ) {
+        _test_spawn_oracle_on_invalid_args(|args| args.resize(length, 0), seed);
+    }
+
+    #[test]
+    fn test_spawn_oracle_on_invalid_flag(seed: u64, flag in 2u8..) {
+        _test_spawn_oracle_on_invalid_args(|args| args.push(flag), seed);
+    }
+}
+
+fn _delegate_lock(pubkey_hash: [u8; 20], signing_key: SigningKey) -> LockSetup {
+    let contract_bin: Bytes = Loader::default().load_binary("delegate-lock-ckb-tx-message-all");
+    let verifier_bin: Bytes = Loader::default().load_binary("secp256k1-blake160-verifier");
+
+    let mut args = CellOutput::calc_data_hash(&verifier_bin)
+        .as_bytes()
+        .to_vec();
+    // secp256k1 with blake160 public key hash
+    args.push(0);
+    args.extend_from_slice(&pubkey_hash);
+
+    LockSetup::new(contract_bin)
+        .args(args.into())
+        .dep_bin(verifier_bin)
+        .signer(secp256k1_signer(signing_key))
+}
+
+fn _test_delegate_lock_on_valid_tx_with_witness(seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let key = secp256k1_key(seed);
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _delegate_lock(secp256k1_blake160(&key), key),
+        success_bin,
+        seed,
+    );
+
+    // run
+    let cycles = context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn _test_delegate_lock_with_wrong_key(seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let key = secp256k1_key(seed);
+    let wrong_key = secp256k1_key(seed.wrapping_add(1));
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _delegate_lock(secp256k1_blake160(&key), wrong_key),
+        success_bin,
+        seed,
+    );
+
+    // run to a failure, with the exit code forwarded from the verifier
+    assert_script_error(
+        context.verify_tx(&tx, 100_000_000),
+        ERROR_PUBKEY_HASH_MISMATCH,
+    );
+}
+
+fn _test_delegate_lock_on_unsigned_tx_data(seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let key = secp256k1_key(seed);
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _delegate_lock(secp256k1_blake160(&key), key),
+        success_bin,
+        seed,
+    );
+
+    // Append an output cell so tx changes, while staying structurally valid
+    let tx = {
+        tx.as_advanced_builder()
+            .output(CellOutput::new_builder().build())
+            .output_data(Bytes::new().pack())
+            .build()
+    };
+
+    // run to a failure, with the exit code forwarded from the verifier
+    assert_script_error(
+        context.verify_tx(&tx, 100_000_000),
+        ERROR_PUBKEY_HASH_MISMATCH,
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_delegate_lock_on_valid_tx_with_witness(seed: u64) {
+        _test_delegate_lock_on_valid_tx_with_witness(seed);
+    }
+
+    #[test]
+    fn test_delegate_lock_with_wrong_key(seed: u64) {
+        _test_delegate_lock_with_wrong_key(seed);
+    }
+
+    #[test]
+    fn test_delegate_lock_on_unsigned_tx_data(seed: u64) {
+        _test_delegate_lock_on_unsigned_tx_data(seed);
+    }
+}
+
+fn _secp256k1_lock(pubkey_hash: [u8; 20], signing_key: SigningKey) -> LockSetup {
+    let contract_bin: Bytes = Loader::default().load_binary("secp256k1-lock-ckb-tx-message-all");
+
+    LockSetup::new(contract_bin)
+        .args(pubkey_hash.to_vec().into())
+        .signer(secp256k1_signer(signing_key))
+}
+
+fn _test_secp256k1_lock_on_valid_tx_with_witness(seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let key = secp256k1_key(seed);
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _secp256k1_lock(secp256k1_blake160(&key), key),
+        success_bin,
+        seed,
+    );
+
+    // run
+    let cycles = context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn _test_secp256k1_lock_with_wrong_key(seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let key = secp256k1_key(seed);
+    let wrong_key = secp256k1_key(seed.wrapping_add(1));
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _secp256k1_lock(secp256k1_blake160(&key), wrong_key),
+        success_bin,
+        seed,
+    );
+
+    // run to a failure
+    assert_script_error(
+        context.verify_tx(&tx, 100_000_000),
+        ERROR_PUBKEY_HASH_MISMATCH,
+    );
+}
+
+fn _test_secp256k1_lock_on_tampered_message(seed: u64) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let key = secp256k1_key(seed);
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _secp256k1_lock(secp256k1_blake160(&key), key),
+        success_bin,
+        seed,
+    );
+
+    // Append an output cell so tx changes, while staying structurally valid
+    let tx = {
+        tx.as_advanced_builder()
+            .output(CellOutput::new_builder().build())
+            .output_data(Bytes::new().pack())
+            .build()
+    };
+
+    // run to a failure
+    assert_script_error(
+        context.verify_tx(&tx, 100_000_000),
+        ERROR_PUBKEY_HASH_MISMATCH,
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_secp256k1_lock_on_valid_tx_with_witness(seed: u64) {
+        _test_secp256k1_lock_on_valid_tx_with_witness(seed);
+    }
+
+    #[test]
+    fn test_secp256k1_lock_with_wrong_key(seed: u64) {
+        _test_secp256k1_lock_with_wrong_key(seed);
+    }
+
+    #[test]
+    fn test_secp256k1_lock_on_tampered_message(seed: u64) {
+        _test_secp256k1_lock_on_tampered_message(seed);
+    }
+}
+
+// Exit codes of multisig-lock-ckb-tx-message-all
+const MULTISIG_ERROR_PUBKEY_HASH_MISMATCH: i8 = 4;
+const MULTISIG_ERROR_WRONG_SIGNATURE_COUNT: i8 = 8;
+
+/// Build a random M-of-N multisig config, together with indices of M
+/// signers in ascending order, which always include the first R keys.
+fn _multisig_setup(seed: u64, min_threshold: u8) -> (MultisigConfig, Vec<SigningKey>, Vec<usize>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let pubkeys: u8 = rng.gen_range(std::cmp::max(min_threshold, 1)..=5);
+    let threshold: u8 = rng.gen_range(std::cmp::max(min_threshold, 1)..=pubkeys);
+    let require_first_n: u8 = rng.gen_range(0..=threshold);
+
+    let keys: Vec<SigningKey> = (0..pubkeys as u64)
+        .map(|i| secp256k1_key(seed.wrapping_add(i)))
+        .collect();
+    let config = MultisigConfig::new(require_first_n, threshold, &keys);
+
+    let mut optional_signers: Vec<usize> = (require_first_n as usize..pubkeys as usize).collect();
+    optional_signers.shuffle(&mut rng);
+    let mut signers: Vec<usize> = (0..require_first_n as usize)
+        .chain(
+            optional_signers
+                .into_iter()
+                .take((threshold - require_first_n) as usize),
+        )
+        .collect();
+    signers.sort();
+
+    (config, keys, signers)
+}
+
+fn _multisig_lock(config: MultisigConfig, keys: &[SigningKey], signers: &[usize]) -> LockSetup {
+    let contract_bin: Bytes = Loader::default().load_binary("multisig-lock-ckb-tx-message-all");
+    let signing_keys = signers.iter().map(|i| keys[*i].clone()).collect();
+
+    LockSetup::new(contract_bin)
+        .args(config.blake160().to_vec().into())
+        .signer(multisig_signer(config, signing_keys))
+}
+
+fn _build_multisig_tx(
+    seed: u64,
+    min_threshold: u8,
+    signers: impl Fn(Vec<usize>) -> Vec<usize>,
+) -> (Context, TransactionView) {
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let (config, keys, valid_signers) = _multisig_setup(seed, min_threshold);
+
+    let (context, tx, _) = build_tx_with_witness_data_with_lock(
+        _multisig_lock(config, &keys, &signers(valid_signers)),
+        success_bin,
+        seed,
+    );
+    (context, tx)
+}
+
+fn _test_multisig_lock_on_threshold_signatures(seed: u64) {
+    let (context, tx) = _build_multisig_tx(seed, 1, |signers| signers);
+
+    // run
+    let cycles = context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn _test_multisig_lock_on_invalid_signatures(
+    seed: u64,
+    min_threshold: u8,
+    signers: impl Fn(Vec<usize>) -> Vec<usize>,
+    exit_code: i8,
+) {
+    let (context, tx) = _build_multisig_tx(seed, min_threshold, signers);
+
+    // run to a failure
+    assert_script_error(context.verify_tx(&tx, 100_000_000), exit_code);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_multisig_lock_on_threshold_signatures(seed: u64) {
+        _test_multisig_lock_on_threshold_signatures(seed);
+    }
+
+    #[test]
+    fn test_multisig_lock_below_threshold(seed: u64) {
+        _test_multisig_lock_on_invalid_signatures(
+            seed,
+            1,
+            |mut signers| {
+                signers.pop();
+                signers
+            },
+            MULTISIG_ERROR_WRONG_SIGNATURE_COUNT,
+        );
+    }
+
+    #[test]
+    fn test_multisig_lock_with_duplicate_signer(seed: u64) {
+        _test_multisig_lock_on_invalid_signatures(
+            seed,
+            2,
+            |mut signers| {
+                let last = signers.len() - 1;
+                signers[last] = signers[last - 1];
+                signers
+            },
+            MULTISIG_ERROR_PUBKEY_HASH_MISMATCH,
+        );
+    }
+
+    #[test]
+    fn test_multisig_lock_with_signatures_in_wrong_order(seed: u64) {
+        _test_multisig_lock_on_invalid_signatures(
+            seed,
+            2,
+            |mut signers| {
+                signers.reverse();
+                signers
+            },
+            MULTISIG_ERROR_PUBKEY_HASH_MISMATCH,
+        );
+    }
+}
+
+fn _test_type_assert_on_valid_tx(algorithm: u8, seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("type-assert-ckb-tx-message-all");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let signer = HashAlgorithm::try_from(algorithm).expect("hash algorithm");
+    let lock = LockSetup::new(contract_bin)
+        .args(vec![algorithm].into())
+        .signer(hash_signer(signer));
+
+    let (context, tx, _) = build_tx_with_type_script_with_lock(lock, success_bin, seed);
+
+    // run
+    let cycles = context
+        .verify_tx(&tx, 100_000_000)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn _test_type_assert_on_tampered_tx(seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("type-assert-ckb-tx-message-all");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (context, tx, _) = build_tx_with_type_script(contract_bin, success_bin, seed);
+
+    // Append a witness, which is covered by CKB_TX_MESSAGE_ALL
+    let tx = tx
+        .as_advanced_builder()
+        .witness(Bytes::from(vec![0u8; 8]).pack())
+        .build();
+
+    // run to a failure
+    assert_script_error(context.verify_tx(&tx, 100_000_000), ExitCode::HashMismatch);
+}
+
+fn _test_type_assert_on_outputs_only_group(seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("type-assert-ckb-tx-message-all");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+
+    let (mut context, tx, indices) = build_tx_with_type_script(contract_bin, success_bin, seed);
+
+    // Strip the type script from group input cells, leaving a type script
+    // group of output cells only, which has no group witness to load
+    for index in indices {
+        let out_point = tx.inputs().get(index).unwrap().previous_output();
+        let cell = context.cells.get_mut(&out_point).unwrap();
+        cell.0 = cell
+            .0
+            .clone()
+            .as_builder()
+            .type_(None::<Script>.pack())
+            .build();
+    }
+
+    // run to a failure
+    assert_script_error(context.verify_tx(&tx, 100_000_000), ExitCode::Syscall);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 30, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_type_assert_ckb_tx_message_on_valid_tx(seed: u64, algorithm in 0u8..3) {
+        _test_type_assert_on_valid_tx(algorithm, seed);
+    }
+
+    #[test]
+    fn test_type_assert_ckb_tx_message_on_tampered_tx(seed: u64) {
+        _test_type_assert_on_tampered_tx(seed);
+    }
+
+    #[test]
+    fn test_type_assert_ckb_tx_message_on_outputs_only_group(seed: u64) {
+        _test_type_assert_on_outputs_only_group(seed);
+    }
+}
+
+#[cfg(feature = "native-simulator")]
+fn _native_simulators(contract_bin: &Bytes, success_bin: &Bytes) -> crate::Simulators {
+    let loader = Loader::default();
+    crate::Simulators::default()
+        .simulator(
+            contract_bin,
+            loader.simulator_path("rust-assert-ckb-tx-message-all"),
+        )
+        .simulator(success_bin, loader.simulator_path("always-success"))
+}
+
+#[cfg(feature = "native-simulator")]
+fn _test_native_simulator_on_valid_tx<F>(build: F, seed: u64)
+where
+    F: Fn(Bytes, Bytes, u64) -> (Context, TransactionView, Vec<usize>),
+{
+    let contract_bin: Bytes = Loader::default().load_binary("rust-assert-ckb-tx-message-all");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let simulators = _native_simulators(&contract_bin, &success_bin);
+
+    let (context, tx, _) = build(contract_bin, success_bin, seed);
+
+    crate::verify_tx_natively(&context, &tx, &simulators).expect("pass verification");
+}
+
+#[cfg(feature = "native-simulator")]
+fn _test_native_simulator_on_appended_witness_tx(seed: u64) {
+    let contract_bin: Bytes = Loader::default().load_binary("rust-assert-ckb-tx-message-all");
+    let success_bin: Bytes = Loader::default().load_binary("always-success");
+    let simulators = _native_simulators(&contract_bin, &success_bin);
+
+    let (context, tx, _) = build_bare_tx(contract_bin, success_bin, seed);
+
+    // Witnesses beyond input cells are always covered
+    let tx = {
+        let mut builder = tx.as_advanced_builder();
+        for _ in tx.witnesses().len()..=tx.inputs().len() {
+            builder = builder.witness(Bytes::new().pack());
+        }
+        builder.build()
+    };
+
+    let result = crate::verify_tx_natively(&context, &tx, &simulators);
+    assert!(
+        matches!(result, Err(crate::SimulatorError::Script(_, code)) if code == ExitCode::HashMismatch as i8),
+        "{:?}",
+        result
+    );
+}
+
+// Contracts & their native simulators are built via make build, then run:
+//
+// make test CARGO_ARGS="-p tests --features native-simulator native_simulator"
+#[cfg(feature = "native-simulator")]
+proptest! {
+    // Simulators reload the transaction for each script group, which is slow
+    // on large data
+    #![proptest_config(ProptestConfig {
+        cases: 10, .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_native_simulator_on_valid_bare_tx(seed: u64) {
+        _test_native_simulator_on_valid_tx(build_bare_tx, seed);
+    }
+
+    #[test]
+    fn test_native_simulator_on_valid_tx_with_witness(seed: u64) {
+        _test_native_simulator_on_valid_tx(build_tx_with_witness_data, seed);
+    }
+
+    #[test]
+    fn test_native_simulator_on_valid_tx_with_super_large_data(seed: u64) {
+        _test_native_simulator_on_valid_tx(build_tx_with_super_large_data, seed);
+    }
+
+    #[test]
+    fn test_native_simulator_on_appended_witness_tx(seed: u64) {
+        _test_native_simulator_on_appended_witness_tx(seed);
+    }
 }
 
 // generated unit test for contract always_success